@@ -1,56 +1,79 @@
 use itertools::Itertools;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::io::{self, BufRead};
 
-fn find_best_position(positions: &Vec<(i32, i32)>) -> ((i32, i32), usize) {
-    let mut state: HashMap<(i32, i32), usize> =
-        HashMap::from_iter(positions.iter().map(|p| (*p, 0)));
-    for (pos_a, pos_b) in positions.iter().tuple_combinations() {
-        let obstructed = if pos_b.0 - pos_a.0 == 0 {
-            // vertical line
-            ((pos_b.1.min(pos_a.1) + 1)..(pos_b.1.max(pos_a.1)))
-                .any(|y| state.contains_key(&(pos_a.0, y)))
-        } else {
-            let slope = (pos_b.1 - pos_a.1) as f64 / (pos_b.0 - pos_a.0) as f64;
-            ((pos_b.0.min(pos_a.0) + 1)..(pos_b.0.max(pos_a.0)))
-                .map(|x| (x, slope * (x - pos_a.0) as f64 + pos_a.1 as f64))
-                .filter(|&(_, y)| y == (y as i32) as f64)
-                .any(|(x, y)| state.contains_key(&(x, y as i32)))
-        };
-        if !obstructed {
-            *state.get_mut(pos_a).unwrap() += 1;
-            *state.get_mut(pos_b).unwrap() += 1;
-        }
+type Point = (i32, i32);
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
     }
+}
 
-    let (pos, count) = state.iter().max_by_key(|(_, v)| *v).unwrap();
-    (*pos, *count)
+// Reduces the vector between two asteroids so that all asteroids on the same
+// line of sight share a direction
+fn direction(from: &Point, to: &Point) -> Point {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let g = gcd(dx, dy);
+    (dx / g, dy / g)
 }
 
-fn get_distances(positions: &Vec<(i32, i32)>, target: &(i32, i32)) -> Vec<((i32, i32), f64, f64)> {
-    // Calculate distance and angle to each point
-    let mut distances = positions
+// Orders directions clockwise, starting from straight up (y grows downwards)
+fn compare_angles(a: &Point, b: &Point) -> Ordering {
+    let half = |(dx, dy): &Point| !(*dx > 0 || (*dx == 0 && *dy < 0));
+    half(a).cmp(&half(b)).then_with(|| {
+        let cross = a.0 as i64 * b.1 as i64 - a.1 as i64 * b.0 as i64;
+        0.cmp(&cross)
+    })
+}
+
+fn count_visible(asteroids: &[Point], station: &Point) -> usize {
+    asteroids
         .iter()
-        .filter(|&p| p != target)
-        .map(|p| {
-            let dx = (p.0 - target.0) as f64;
-            let dy = (p.1 - target.1) as f64;
-            let dist = dx * dx + dy * dy;
-            (
-                *p,
-                dist,
-                (dy.atan2(dx).to_degrees() + 90.0).rem_euclid(360.0),
-            )
-        })
-        .collect::<Vec<_>>();
-    // Sort by angle first, then distance
-    distances.sort_by(|a, b| (a.2, a.1).partial_cmp(&(b.2, b.1)).unwrap());
-    distances
+        .filter(|&p| p != station)
+        .map(|p| direction(station, p))
+        .unique()
+        .count()
+}
+
+fn find_best_position(asteroids: &[Point]) -> (Point, usize) {
+    asteroids
+        .iter()
+        .map(|p| (*p, count_visible(asteroids, p)))
+        .max_by_key(|(_, count)| *count)
+        .unwrap()
+}
+
+fn vaporisation_order(asteroids: &[Point], station: &Point) -> Vec<Point> {
+    let mut lines: HashMap<Point, Vec<Point>> = HashMap::new();
+    for p in asteroids.iter().filter(|&p| p != station) {
+        lines.entry(direction(station, p)).or_default().push(*p);
+    }
+
+    let mut lines: Vec<(Point, Vec<Point>)> = lines.into_iter().collect();
+    lines.sort_by(|(a, _), (b, _)| compare_angles(a, b));
+    for (_, line) in lines.iter_mut() {
+        // Furthest asteroids first, so we can pop the closest one
+        line.sort_by_key(|p| -((p.0 - station.0).abs() + (p.1 - station.1).abs()));
+    }
+
+    let mut order = vec![];
+    while order.len() < asteroids.len() - 1 {
+        for (_, line) in lines.iter_mut() {
+            if let Some(p) = line.pop() {
+                order.push(p);
+            }
+        }
+    }
+    order
 }
 
 fn main() {
-    let mut input: Vec<(i32, i32)> = vec![];
-    for (y, line) in io::stdin().lock().lines().flatten().enumerate() {
+    let mut input: Vec<Point> = vec![];
+    for (y, line) in io::stdin().lock().lines().map_while(Result::ok).enumerate() {
         for (x, _) in line.chars().enumerate().filter(|&(_, c)| c != '.') {
             input.push((x as i32, y as i32));
         }
@@ -62,28 +85,12 @@ fn main() {
         position, visible_sensors
     );
 
-    let mut distances = get_distances(&input, &position);
-
-    let mut last_angle = -1.0;
-    for i in 1..=200 {
-        if distances.is_empty() {
-            break;
-        }
-        let next = distances
-            .iter()
-            .position(|&(_, _, angle)| angle > last_angle)
-            .unwrap_or(0);
-        let asteroid = &distances[next];
-
-        if i == 200 {
-            println!(
-                "(2) Vaporizing {:?} => {} in turn 200",
-                asteroid.0,
-                asteroid.0 .0 * 100 + asteroid.0 .1
-            );
-        }
-
-        last_angle = asteroid.2;
-        distances.remove(next);
+    let order = vaporisation_order(&input, &position);
+    if let Some(asteroid) = order.get(199) {
+        println!(
+            "(2) Vaporizing {:?} => {} in turn 200",
+            asteroid,
+            asteroid.0 * 100 + asteroid.1
+        );
     }
 }
@@ -0,0 +1,127 @@
+use std::env;
+use std::io::{self, BufRead};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Moon {
+    position: Vec<i64>,
+    velocity: Vec<i64>,
+}
+
+impl Moon {
+    fn new(position: Vec<i64>) -> Self {
+        let velocity = vec![0; position.len()];
+        Moon { position, velocity }
+    }
+
+    fn energy(&self) -> i64 {
+        let potential: i64 = self.position.iter().map(|p| p.abs()).sum();
+        let kinetic: i64 = self.velocity.iter().map(|v| v.abs()).sum();
+        potential * kinetic
+    }
+}
+
+fn apply_gravity(moons: &mut [Moon], axis: usize) {
+    for i in 0..moons.len() {
+        for j in (i + 1)..moons.len() {
+            let delta = (moons[j].position[axis] - moons[i].position[axis]).signum();
+            moons[i].velocity[axis] += delta;
+            moons[j].velocity[axis] -= delta;
+        }
+    }
+}
+
+fn step_axis(moons: &mut [Moon], axis: usize) {
+    apply_gravity(moons, axis);
+    for moon in moons.iter_mut() {
+        moon.position[axis] += moon.velocity[axis];
+    }
+}
+
+fn step(moons: &mut [Moon]) {
+    for axis in 0..moons[0].position.len() {
+        step_axis(moons, axis);
+    }
+}
+
+fn axis_state(moons: &[Moon], axis: usize) -> Vec<(i64, i64)> {
+    moons
+        .iter()
+        .map(|moon| (moon.position[axis], moon.velocity[axis]))
+        .collect()
+}
+
+fn find_axis_period(moons: &[Moon], axis: usize) -> u64 {
+    // The simulation is reversible, so every axis returns to its initial state
+    let mut moons = moons.to_vec();
+    let initial_state = axis_state(&moons, axis);
+    let mut steps = 0;
+    loop {
+        step_axis(&mut moons, axis);
+        steps += 1;
+        if axis_state(&moons, axis) == initial_state {
+            return steps;
+        }
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+fn find_period(moons: &[Moon]) -> u64 {
+    (0..moons[0].position.len())
+        .map(|axis| find_axis_period(moons, axis))
+        .fold(1, lcm)
+}
+
+fn parse_moon(line: &str) -> Moon {
+    Moon::new(
+        line.split(|c: char| !(c.is_ascii_digit() || c == '-'))
+            .flat_map(str::parse)
+            .collect(),
+    )
+}
+
+fn main() {
+    let steps = env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse::<usize>().ok())
+        .unwrap_or(1000);
+
+    let moons: Vec<Moon> = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_moon(&line))
+        .collect();
+    assert!(
+        moons
+            .iter()
+            .all(|moon| moon.position.len() == moons[0].position.len()),
+        "All moons should have the same number of dimensions"
+    );
+
+    let mut state = moons.clone();
+    for _ in 0..steps {
+        step(&mut state);
+    }
+    let energy: i64 = state.iter().map(Moon::energy).sum();
+    println!(
+        "(1) After {} steps, the total energy in the system is {}",
+        steps, energy
+    );
+
+    println!(
+        "(2) The moons return to a previous state after {} steps",
+        find_period(&moons)
+    );
+}
@@ -0,0 +1,111 @@
+use itertools::Itertools;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead};
+
+const ORE_AVAILABLE: u64 = 1_000_000_000_000;
+
+#[derive(Debug)]
+struct Reaction {
+    quantity: u64,
+    inputs: Vec<(String, u64)>,
+}
+
+type Reactions = HashMap<String, Reaction>;
+
+#[derive(Debug, Default)]
+struct Plan {
+    ore: u64,
+    runs: HashMap<String, u64>,
+    surplus: HashMap<String, u64>,
+}
+
+fn parse_chemical(input: &str) -> (String, u64) {
+    if let [quantity, name] = input.trim().split(' ').collect::<Vec<&str>>()[..] {
+        (name.to_string(), quantity.parse().unwrap())
+    } else {
+        panic!("Unexpected chemical {}", input)
+    }
+}
+
+fn parse_reaction(line: &str) -> (String, Reaction) {
+    if let [inputs, output] = line.split(" => ").collect::<Vec<&str>>()[..] {
+        let (name, quantity) = parse_chemical(output);
+        let inputs = inputs.split(',').map(parse_chemical).collect();
+        (name, Reaction { quantity, inputs })
+    } else {
+        panic!("Unexpected reaction {}", line)
+    }
+}
+
+fn plan_production(reactions: &Reactions, fuel: u64) -> Plan {
+    let mut plan = Plan::default();
+    let mut queue = VecDeque::from([("FUEL".to_string(), fuel)]);
+    while let Some((chemical, mut needed)) = queue.pop_front() {
+        if chemical == "ORE" {
+            plan.ore += needed;
+            continue;
+        }
+
+        let leftover = plan.surplus.entry(chemical.clone()).or_default();
+        let used = needed.min(*leftover);
+        *leftover -= used;
+        needed -= used;
+        if needed == 0 {
+            continue;
+        }
+
+        let reaction = &reactions[&chemical];
+        let runs = needed.div_ceil(reaction.quantity);
+        *leftover += runs * reaction.quantity - needed;
+        *plan.runs.entry(chemical).or_default() += runs;
+        for (input, quantity) in &reaction.inputs {
+            queue.push_back((input.clone(), quantity * runs));
+        }
+    }
+    plan.surplus.retain(|_, quantity| *quantity > 0);
+    plan
+}
+
+fn max_fuel(reactions: &Reactions, ore: u64) -> u64 {
+    let (mut low, mut high) = (0, ore);
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if plan_production(reactions, mid).ore <= ore {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+fn print_plan(plan: &Plan) {
+    for (chemical, runs) in plan.runs.iter().sorted() {
+        println!(
+            "  {:>6} x {} (surplus {})",
+            runs,
+            chemical,
+            plan.surplus.get(chemical).unwrap_or(&0)
+        );
+    }
+}
+
+fn main() {
+    let reactions: Reactions = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_reaction(&line))
+        .collect();
+
+    let plan = plan_production(&reactions, 1);
+    println!("(1) Producing 1 FUEL requires {} ORE", plan.ore);
+    print_plan(&plan);
+
+    println!(
+        "(2) With {} ORE, we can produce {} FUEL",
+        ORE_AVAILABLE,
+        max_fuel(&reactions, ORE_AVAILABLE)
+    );
+}
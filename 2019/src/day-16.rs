@@ -0,0 +1,116 @@
+use itertools::Itertools;
+use std::env;
+use std::io::{self, BufRead};
+
+const BASE_PATTERN: [i32; 4] = [0, 1, 0, -1];
+
+// Each phase function takes the digits of the signal starting at `offset` and
+// returns the digits of the next phase starting at the same offset. Digits
+// before the offset never influence later digits, so they can be omitted.
+type Phase = fn(&[i32], usize) -> Vec<i32>;
+
+fn naive_phase(signal: &[i32], offset: usize) -> Vec<i32> {
+    (0..signal.len())
+        .map(|i| {
+            let position = offset + i + 1;
+            let sum: i32 = signal
+                .iter()
+                .enumerate()
+                .skip(i)
+                .map(|(j, digit)| digit * BASE_PATTERN[((offset + j + 1) / position) % 4])
+                .sum();
+            sum.abs() % 10
+        })
+        .collect()
+}
+
+fn suffix_phase(signal: &[i32], offset: usize) -> Vec<i32> {
+    // In the second half of the signal, the pattern is 0 before the digit and
+    // 1 after it, so each digit is the sum of all digits following it.
+    assert!(
+        offset + signal.len() <= offset * 2 + 1,
+        "Offset {} is not in the second half of the signal",
+        offset
+    );
+    let mut output = vec![0; signal.len()];
+    let mut sum = 0;
+    for i in (0..signal.len()).rev() {
+        sum = (sum + signal[i]) % 10;
+        output[i] = sum;
+    }
+    output
+}
+
+fn run_phases(signal: &[i32], offset: usize, phases: usize, phase: Phase) -> Vec<i32> {
+    (0..phases).fold(signal[offset..].to_vec(), |signal, _| {
+        phase(&signal, offset)
+    })
+}
+
+fn repeat_signal(signal: &[i32], repeats: usize) -> Vec<i32> {
+    signal
+        .iter()
+        .cycle()
+        .take(signal.len() * repeats)
+        .copied()
+        .collect()
+}
+
+fn to_string(digits: &[i32]) -> String {
+    digits.iter().take(8).join("")
+}
+
+fn cross_check(signal: &[i32], repeats: usize) {
+    let repeated = repeat_signal(signal, repeats);
+    let offset = repeated.len().div_ceil(2);
+    let naive = run_phases(&repeated, offset, 100, naive_phase);
+    let optimised = run_phases(&repeated, offset, 100, suffix_phase);
+    println!(
+        "Repeating {} times from offset {}: naive {}, optimised {}",
+        repeats,
+        offset,
+        to_string(&naive),
+        to_string(&optimised)
+    );
+    assert_eq!(naive, optimised);
+}
+
+fn main() -> io::Result<()> {
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    let signal: Vec<i32> = line
+        .trim()
+        .chars()
+        .map(|c| c.to_digit(10).unwrap() as i32)
+        .collect();
+
+    let args: Vec<String> = env::args().collect();
+    if let [_, flag, repeats] = &args[..] {
+        if flag == "--check" {
+            cross_check(&signal, repeats.parse().unwrap());
+            return Ok(());
+        }
+    }
+
+    let output = run_phases(&signal, 0, 100, naive_phase);
+    println!(
+        "(1) After 100 phases, the output starts with {}",
+        to_string(&output)
+    );
+
+    let total = signal.len() * 10000;
+    let offset: usize = to_string(&signal[..7.min(signal.len())])
+        .parse()
+        .unwrap_or(0);
+    if offset >= total || total > offset * 2 + 1 {
+        println!(
+            "(2) The message offset {} is not in the second half of the {} digit signal",
+            offset, total
+        );
+    } else {
+        let output = run_phases(&repeat_signal(&signal, 10000), offset, 100, suffix_phase);
+        println!("(2) The embedded message is {}", to_string(&output));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::io::{self, BufRead};
+
+type Grid = Vec<Vec<char>>;
+type Point = (usize, usize);
+
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    key: usize,
+    distance: usize,
+    doors: u32,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct State {
+    robots: Vec<usize>,
+    keys: u32,
+}
+
+#[derive(Eq, PartialEq)]
+struct Vertex {
+    cost: usize,
+    state: State,
+}
+
+impl Ord for Vertex {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Vertex {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Nodes 0..26 are the keys, the robots' starting positions follow
+fn node_index(c: char, starts: &[Point], position: Point) -> Option<usize> {
+    match c {
+        'a'..='z' => Some(c as usize - 'a' as usize),
+        '@' => starts.iter().position(|p| *p == position).map(|r| 26 + r),
+        _ => None,
+    }
+}
+
+fn find_edges(grid: &Grid, start: Point) -> Vec<Edge> {
+    let mut edges = vec![];
+    let mut seen = vec![vec![false; grid[0].len()]; grid.len()];
+    let mut queue = VecDeque::from([(start, 0, 0)]);
+    seen[start.1][start.0] = true;
+    while let Some(((x, y), distance, doors)) = queue.pop_front() {
+        let c = grid[y][x];
+        let doors = match c {
+            'A'..='Z' => doors | (1 << (c as u32 - 'A' as u32)),
+            'a'..='z' if distance > 0 => {
+                edges.push(Edge {
+                    key: c as usize - 'a' as usize,
+                    distance,
+                    doors,
+                });
+                doors
+            }
+            _ => doors,
+        };
+        for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if grid[ny][nx] != '#' && !seen[ny][nx] {
+                seen[ny][nx] = true;
+                queue.push_back(((nx, ny), distance + 1, doors));
+            }
+        }
+    }
+    edges
+}
+
+fn split_vault(grid: &mut Grid, starts: &mut Vec<Point>) -> bool {
+    let (x, y) = starts[0];
+    let is_open = (y - 1..=y + 1).all(|y| (x - 1..=x + 1).all(|x| "@.".contains(grid[y][x])));
+    if !is_open {
+        return false;
+    }
+    for (wx, wy) in [(x, y), (x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+        grid[wy][wx] = '#';
+    }
+    *starts = vec![
+        (x - 1, y - 1),
+        (x + 1, y - 1),
+        (x - 1, y + 1),
+        (x + 1, y + 1),
+    ];
+    for (sx, sy) in starts.iter() {
+        grid[*sy][*sx] = '@';
+    }
+    true
+}
+
+fn collect_keys(grid: &Grid, starts: &[Point]) -> Option<(usize, String)> {
+    let mut graph: HashMap<usize, Vec<Edge>> = HashMap::new();
+    let mut all_keys = 0_u32;
+    for (y, row) in grid.iter().enumerate() {
+        for (x, c) in row.iter().enumerate() {
+            if let Some(node) = node_index(*c, starts, (x, y)) {
+                graph.insert(node, find_edges(grid, (x, y)));
+                if node < 26 {
+                    all_keys |= 1 << node;
+                }
+            }
+        }
+    }
+
+    let start = State {
+        robots: (0..starts.len()).map(|r| 26 + r).collect(),
+        keys: 0,
+    };
+    let mut distances: HashMap<State, usize> = HashMap::from([(start.clone(), 0)]);
+    let mut previous: HashMap<State, (State, usize)> = HashMap::new();
+    let mut heap = BinaryHeap::from([Vertex {
+        cost: 0,
+        state: start,
+    }]);
+
+    while let Some(Vertex { cost, state }) = heap.pop() {
+        if state.keys == all_keys {
+            let mut order = vec![];
+            let mut curr = &state;
+            while let Some((prev, key)) = previous.get(curr) {
+                order.push((b'a' + *key as u8) as char);
+                curr = prev;
+            }
+            return Some((cost, order.into_iter().rev().collect()));
+        }
+        if cost > distances[&state] {
+            continue;
+        }
+
+        for (robot, node) in state.robots.iter().enumerate() {
+            for edge in &graph[node] {
+                if state.keys & (1 << edge.key) != 0 || edge.doors & !state.keys != 0 {
+                    continue;
+                }
+                let mut next = state.clone();
+                next.robots[robot] = edge.key;
+                next.keys |= 1 << edge.key;
+                let next_cost = cost + edge.distance;
+                if next_cost < *distances.get(&next).unwrap_or(&usize::MAX) {
+                    distances.insert(next.clone(), next_cost);
+                    previous.insert(next.clone(), (state.clone(), edge.key));
+                    heap.push(Vertex {
+                        cost: next_cost,
+                        state: next,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn main() {
+    let mut grid: Grid = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().collect())
+        .collect();
+    let mut starts: Vec<Point> = vec![];
+    for (y, row) in grid.iter().enumerate() {
+        for (x, c) in row.iter().enumerate() {
+            if *c == '@' {
+                starts.push((x, y));
+            }
+        }
+    }
+
+    if starts.len() == 1 {
+        if let Some((steps, order)) = collect_keys(&grid, &starts) {
+            println!(
+                "(1) Collecting all keys takes {} steps, in order {}",
+                steps, order
+            );
+        }
+        if !split_vault(&mut grid, &mut starts) {
+            return;
+        }
+    }
+
+    if let Some((steps, order)) = collect_keys(&grid, &starts) {
+        println!(
+            "(2) Collecting all keys with {} robots takes {} steps, in order {}",
+            starts.len(),
+            steps,
+            order
+        );
+    } else {
+        println!("(2) The robots can not collect all keys");
+    }
+}
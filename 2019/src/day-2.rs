@@ -1,8 +1,22 @@
 use itertools::iproduct;
+use std::env;
 use std::io::{self, BufRead};
+use std::ops::Range;
 
-fn execute_program(base_memory: &Vec<u32>, noun: u32, verb: u32) -> u32 {
-    let mut memory = base_memory.clone();
+const EXPECTED_OUTPUT: i64 = 19690720;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    BruteForce,
+    // Heuristic: assumes output = a * noun + b * verb + c and checks the model
+    // on the edges of the range only, so a program that is nonlinear inside
+    // the range can still lose solutions. Falls back to brute force when the
+    // check fails. Only used when asked for with `linear`.
+    Linear,
+}
+
+fn execute_program(base_memory: &[i64], noun: i64, verb: i64) -> i64 {
+    let mut memory = base_memory.to_vec();
     memory[1] = noun;
     memory[2] = verb;
 
@@ -29,22 +43,77 @@ fn execute_program(base_memory: &Vec<u32>, noun: u32, verb: u32) -> u32 {
     memory[0]
 }
 
+fn brute_force(memory: &[i64], range: &Range<i64>, expected: i64) -> Vec<(i64, i64)> {
+    iproduct!(range.clone(), range.clone())
+        .filter(|&(noun, verb)| execute_program(memory, noun, verb) == expected)
+        .collect()
+}
+
+fn solve_linear(memory: &[i64], range: &Range<i64>, expected: i64) -> Option<Vec<(i64, i64)>> {
+    let c = execute_program(memory, 0, 0);
+    let a = execute_program(memory, 1, 0) - c;
+    let b = execute_program(memory, 0, 1) - c;
+
+    let (first, last) = (range.start, range.end - 1);
+    let is_linear = range
+        .clone()
+        .flat_map(|n| vec![(n, first), (n, last), (first, n), (last, n)])
+        .all(|(noun, verb)| execute_program(memory, noun, verb) == a * noun + b * verb + c);
+    if !is_linear {
+        return None;
+    }
+
+    let solutions: Vec<(i64, i64)> = range
+        .clone()
+        .flat_map(|noun| {
+            let remainder = expected - c - a * noun;
+            match b {
+                0 if remainder == 0 => range.clone().map(|verb| (noun, verb)).collect(),
+                0 => vec![],
+                _ if remainder % b == 0 && range.contains(&(remainder / b)) => {
+                    vec![(noun, remainder / b)]
+                }
+                _ => vec![],
+            }
+        })
+        .collect();
+    solutions
+        .iter()
+        .all(|&(noun, verb)| execute_program(memory, noun, verb) == expected)
+        .then_some(solutions)
+}
+
+fn search(memory: &[i64], range: Range<i64>, expected: i64, strategy: Strategy) -> Vec<(i64, i64)> {
+    match strategy {
+        Strategy::BruteForce => brute_force(memory, &range, expected),
+        Strategy::Linear => solve_linear(memory, &range, expected).unwrap_or_else(|| {
+            println!("The program is not linear in its inputs, falling back to brute force");
+            brute_force(memory, &range, expected)
+        }),
+    }
+}
+
 fn main() -> io::Result<()> {
+    let strategy = match env::args().nth(1).as_deref() {
+        Some("linear") => Strategy::Linear,
+        _ => Strategy::BruteForce,
+    };
+
     let mut line = String::new();
     io::stdin().lock().read_line(&mut line)?;
 
-    let memory = line.split(',').flat_map(str::parse).collect();
+    let memory: Vec<i64> = line.trim().split(',').flat_map(str::parse).collect();
     println!(
         "(1) The first entry in memory is {}",
         execute_program(&memory, 12, 2)
     );
 
-    let answer = iproduct!(0..100, 0..100)
-        .find(|(noun, verb)| execute_program(&memory, *noun, *verb) == 19690720);
-    if let Some((noun, verb)) = answer {
+    for (noun, verb) in search(&memory, 0..100, EXPECTED_OUTPUT, strategy) {
         println!(
-            "(2) Using noun {} and verb {} leads to the expected output",
-            noun, verb
+            "(2) Using noun {} and verb {} leads to the expected output => {}",
+            noun,
+            verb,
+            100 * noun + verb
         );
     }
 
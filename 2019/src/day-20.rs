@@ -0,0 +1,229 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::env;
+use std::io::{self, BufRead};
+
+type Grid = Vec<Vec<char>>;
+type Point = (usize, usize);
+
+#[derive(Debug)]
+struct Portal {
+    label: String,
+    outer: bool,
+    position: Point,
+}
+
+#[derive(Eq, PartialEq)]
+struct Vertex {
+    cost: usize,
+    node: usize,
+    level: usize,
+}
+
+impl Ord for Vertex {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Vertex {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+struct Maze {
+    portals: Vec<Portal>,
+    // Walking distances to the portals reachable on the same level
+    edges: Vec<Vec<(usize, usize)>>,
+    partners: Vec<Option<usize>>,
+}
+
+fn find_portals(grid: &Grid) -> Vec<Portal> {
+    let (width, height) = (grid[0].len(), grid.len());
+    let mut portals = vec![];
+    for y in 2..height - 2 {
+        for x in 2..width - 2 {
+            if grid[y][x] != '.' {
+                continue;
+            }
+            // The two letters of each label are read left-to-right or top-to-bottom
+            let labels = [
+                (grid[y - 2][x], grid[y - 1][x]),
+                (grid[y + 1][x], grid[y + 2][x]),
+                (grid[y][x - 2], grid[y][x - 1]),
+                (grid[y][x + 1], grid[y][x + 2]),
+            ];
+            for (a, b) in labels {
+                if a.is_ascii_uppercase() && b.is_ascii_uppercase() {
+                    portals.push(Portal {
+                        label: [a, b].iter().collect(),
+                        outer: x == 2 || y == 2 || x == width - 3 || y == height - 3,
+                        position: (x, y),
+                    });
+                }
+            }
+        }
+    }
+    portals
+}
+
+fn find_distances(grid: &Grid, portals: &[Portal], start: Point) -> Vec<(usize, usize)> {
+    let mut distances = vec![];
+    let mut seen = vec![vec![false; grid[0].len()]; grid.len()];
+    let mut queue = VecDeque::from([(start, 0)]);
+    seen[start.1][start.0] = true;
+    while let Some(((x, y), distance)) = queue.pop_front() {
+        if let Some(portal) = portals.iter().position(|p| p.position == (x, y)) {
+            if distance > 0 {
+                distances.push((portal, distance));
+            }
+        }
+        for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if grid[ny][nx] == '.' && !seen[ny][nx] {
+                seen[ny][nx] = true;
+                queue.push_back(((nx, ny), distance + 1));
+            }
+        }
+    }
+    distances
+}
+
+impl Maze {
+    fn parse(grid: &Grid) -> Self {
+        let portals = find_portals(grid);
+        let edges = portals
+            .iter()
+            .map(|portal| find_distances(grid, &portals, portal.position))
+            .collect();
+        let partners = portals
+            .iter()
+            .enumerate()
+            .map(|(i, portal)| {
+                portals
+                    .iter()
+                    .enumerate()
+                    .position(|(j, other)| i != j && other.label == portal.label)
+            })
+            .collect();
+        Maze {
+            portals,
+            edges,
+            partners,
+        }
+    }
+
+    fn find(&self, label: &str) -> usize {
+        self.portals.iter().position(|p| p.label == label).unwrap()
+    }
+
+    // Returns the length of the path, and the (portal, level) steps along it
+    fn solve(&self, recursive: bool, max_depth: usize) -> Option<(usize, Vec<(usize, usize)>)> {
+        let (start, end) = (self.find("AA"), self.find("ZZ"));
+        let mut distances: HashMap<(usize, usize), usize> = HashMap::from([((start, 0), 0)]);
+        let mut previous: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut heap = BinaryHeap::from([Vertex {
+            cost: 0,
+            node: start,
+            level: 0,
+        }]);
+
+        while let Some(Vertex { cost, node, level }) = heap.pop() {
+            if node == end && level == 0 {
+                let mut path = vec![(node, level)];
+                while let Some(prev) = previous.get(path.last().unwrap()) {
+                    path.push(*prev);
+                }
+                path.reverse();
+                return Some((cost, path));
+            }
+            if cost > distances[&(node, level)] {
+                continue;
+            }
+
+            let mut neighbours: Vec<(usize, usize, usize)> = self.edges[node]
+                .iter()
+                .map(|&(next, distance)| (next, level, distance))
+                .collect();
+            if let Some(partner) = self.partners[node] {
+                match (recursive, self.portals[node].outer) {
+                    (false, _) => neighbours.push((partner, level, 1)),
+                    (true, false) if level < max_depth => neighbours.push((partner, level + 1, 1)),
+                    (true, true) if level > 0 => neighbours.push((partner, level - 1, 1)),
+                    _ => {}
+                }
+            }
+
+            for (next, next_level, distance) in neighbours {
+                let next_cost = cost + distance;
+                if next_cost < *distances.get(&(next, next_level)).unwrap_or(&usize::MAX) {
+                    distances.insert((next, next_level), next_cost);
+                    previous.insert((next, next_level), (node, level));
+                    heap.push(Vertex {
+                        cost: next_cost,
+                        node: next,
+                        level: next_level,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn print_path(&self, path: &[(usize, usize)]) {
+        for window in path.windows(2) {
+            let ((from, from_level), (to, to_level)) = (window[0], window[1]);
+            let portal = &self.portals[to];
+            if from_level != to_level {
+                println!("  Teleport through {} to level {}", portal.label, to_level);
+            } else {
+                let distance = self.edges[from].iter().find(|(p, _)| *p == to).unwrap().1;
+                println!(
+                    "  Walk from {} to {} ({}) on level {} in {} steps",
+                    self.portals[from].label,
+                    portal.label,
+                    if portal.outer { "outer" } else { "inner" },
+                    from_level,
+                    distance
+                );
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut grid: Grid = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().collect())
+        .collect();
+    let width = grid.iter().map(|row| row.len()).max().unwrap();
+    for row in grid.iter_mut() {
+        row.resize(width, ' ');
+    }
+
+    let maze = Maze::parse(&grid);
+    let max_depth = env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse::<usize>().ok())
+        .unwrap_or(maze.portals.len());
+
+    if let Some((steps, _)) = maze.solve(false, 0) {
+        println!("(1) It takes {} steps to get from AA to ZZ", steps);
+    }
+
+    if let Some((steps, path)) = maze.solve(true, max_depth) {
+        println!(
+            "(2) It takes {} steps to get from AA to ZZ in the recursive maze",
+            steps
+        );
+        maze.print_path(&path);
+    } else {
+        println!(
+            "(2) There is no path from AA to ZZ within {} levels",
+            max_depth
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::io::{self, BufRead};
+
+const SMALL_DECK: i128 = 10007;
+const LARGE_DECK: i128 = 119315633776377;
+const SHUFFLES: u64 = 101741582076661;
+
+// Maps the position of a card before a shuffle to its position afterwards,
+// as `a * x + b (mod m)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Linear {
+    a: i128,
+    b: i128,
+    m: i128,
+}
+
+impl Linear {
+    fn identity(m: i128) -> Self {
+        Linear { a: 1, b: 0, m }
+    }
+
+    fn parse(line: &str, m: i128) -> Self {
+        if line == "deal into new stack" {
+            Linear { a: -1, b: -1, m }
+        } else if let Some(n) = line.strip_prefix("cut ") {
+            Linear {
+                a: 1,
+                b: -n.parse::<i128>().unwrap(),
+                m,
+            }
+        } else if let Some(n) = line.strip_prefix("deal with increment ") {
+            Linear {
+                a: n.parse().unwrap(),
+                b: 0,
+                m,
+            }
+        } else {
+            panic!("Unexpected technique {}", line)
+        }
+        .normalize()
+    }
+
+    fn normalize(self) -> Self {
+        Linear {
+            a: self.a.rem_euclid(self.m),
+            b: self.b.rem_euclid(self.m),
+            m: self.m,
+        }
+    }
+
+    fn apply(&self, x: i128) -> i128 {
+        (self.a * x + self.b).rem_euclid(self.m)
+    }
+
+    // Returns the map that applies `self` first, then `other`
+    fn then(&self, other: &Linear) -> Self {
+        Linear {
+            a: self.a * other.a % self.m,
+            b: (self.b * other.a + other.b) % self.m,
+            m: self.m,
+        }
+    }
+
+    fn pow(&self, mut exponent: u64) -> Self {
+        let mut result = Linear::identity(self.m);
+        let mut base = *self;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.then(&base);
+            }
+            base = base.then(&base);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    // Only exists when the shuffle doesn't deal two cards to the same position
+    fn inverse(&self) -> Option<Self> {
+        let a_inv = mod_inverse(self.a, self.m)?;
+        Some(Linear {
+            a: a_inv,
+            b: (-self.b * a_inv).rem_euclid(self.m),
+            m: self.m,
+        })
+    }
+}
+
+fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (mut old_r, mut r) = (a, m);
+    let (mut old_s, mut s) = (1, 0);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+    (old_r == 1).then(|| old_s.rem_euclid(m))
+}
+
+fn parse_shuffle(techniques: &[String], m: i128) -> Linear {
+    techniques
+        .iter()
+        .map(|line| Linear::parse(line, m))
+        .fold(Linear::identity(m), |acc, technique| acc.then(&technique))
+}
+
+fn main() {
+    let techniques: Vec<String> = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let shuffle = parse_shuffle(&techniques, SMALL_DECK);
+    println!(
+        "(1) After shuffling, card 2019 is at position {}",
+        shuffle.apply(2019)
+    );
+
+    let shuffle = parse_shuffle(&techniques, LARGE_DECK).pow(SHUFFLES);
+    match shuffle.inverse() {
+        Some(inverse) => println!(
+            "(2) After shuffling {} times, card {} ends up in position 2020",
+            SHUFFLES,
+            inverse.apply(2020)
+        ),
+        None => println!(
+            "(2) The shuffle deals several cards to the same position in a deck of {}",
+            LARGE_DECK
+        ),
+    }
+}
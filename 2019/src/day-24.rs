@@ -0,0 +1,147 @@
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::io::{self, BufRead};
+
+const SIZE: i32 = 5;
+const CENTER: u32 = 12;
+
+type Levels = BTreeMap<i32, u32>;
+
+fn bit(x: i32, y: i32) -> u32 {
+    1 << (y * SIZE + x)
+}
+
+fn next_state(alive: bool, neighbours: u32) -> bool {
+    neighbours == 1 || (!alive && neighbours == 2)
+}
+
+fn step(grid: u32) -> u32 {
+    let mut next = 0;
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let neighbours = [(0, -1), (0, 1), (-1, 0), (1, 0)]
+                .iter()
+                .map(|(dx, dy)| (x + dx, y + dy))
+                .filter(|&(nx, ny)| (0..SIZE).contains(&nx) && (0..SIZE).contains(&ny))
+                .filter(|&(nx, ny)| grid & bit(nx, ny) != 0)
+                .count() as u32;
+            if next_state(grid & bit(x, y) != 0, neighbours) {
+                next |= bit(x, y);
+            }
+        }
+    }
+    next
+}
+
+fn find_repeated_layout(grid: u32) -> u32 {
+    let mut seen = HashSet::new();
+    let mut grid = grid;
+    while seen.insert(grid) {
+        grid = step(grid);
+    }
+    grid
+}
+
+// Level `depth + 1` is nested inside the center tile of level `depth`
+fn count_recursive_neighbours(levels: &Levels, depth: i32, x: i32, y: i32) -> u32 {
+    let level = |depth| *levels.get(&depth).unwrap_or(&0);
+    let mut count = 0;
+    for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+        let (nx, ny) = (x + dx, y + dy);
+        if !(0..SIZE).contains(&nx) || !(0..SIZE).contains(&ny) {
+            let outer = level(depth - 1);
+            count += (outer & bit(2 + dx, 2 + dy) != 0) as u32;
+        } else if (nx, ny) == (2, 2) {
+            let inner = level(depth + 1);
+            count += (0..SIZE)
+                .map(|i| match (dx, dy) {
+                    (0, 1) => bit(i, 0),
+                    (0, -1) => bit(i, SIZE - 1),
+                    (1, 0) => bit(0, i),
+                    _ => bit(SIZE - 1, i),
+                })
+                .filter(|mask| inner & mask != 0)
+                .count() as u32;
+        } else {
+            count += (level(depth) & bit(nx, ny) != 0) as u32;
+        }
+    }
+    count
+}
+
+fn step_recursive(levels: &Levels) -> Levels {
+    let min_depth = levels.keys().next().unwrap() - 1;
+    let max_depth = levels.keys().next_back().unwrap() + 1;
+    let mut next = Levels::new();
+    for depth in min_depth..=max_depth {
+        let grid = *levels.get(&depth).unwrap_or(&0);
+        let mut next_grid = 0;
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                if (x, y) == (2, 2) {
+                    continue;
+                }
+                let neighbours = count_recursive_neighbours(levels, depth, x, y);
+                if next_state(grid & bit(x, y) != 0, neighbours) {
+                    next_grid |= bit(x, y);
+                }
+            }
+        }
+        if next_grid != 0 || (min_depth < depth && depth < max_depth) {
+            next.insert(depth, next_grid);
+        }
+    }
+    next
+}
+
+fn print_level(levels: &Levels, depth: i32) {
+    let grid = *levels.get(&depth).unwrap_or(&0);
+    println!("Depth {}:", depth);
+    for y in 0..SIZE {
+        println!(
+            "{}",
+            (0..SIZE)
+                .map(|x| match grid & bit(x, y) {
+                    _ if (x, y) == (2, 2) => '?',
+                    0 => '.',
+                    _ => '#',
+                })
+                .collect::<String>()
+        );
+    }
+}
+
+fn main() {
+    let args: Vec<i32> = env::args().skip(1).flat_map(|arg| arg.parse()).collect();
+    let minutes = args.first().copied().unwrap_or(200);
+
+    let grid = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .flat_map(|line| line.chars().collect::<Vec<_>>())
+        .enumerate()
+        .filter(|(_, c)| *c == '#')
+        .fold(0, |grid, (idx, _)| grid | (1 << idx));
+
+    println!(
+        "(1) The biodiversity rating of the first repeated layout is {}",
+        find_repeated_layout(grid)
+    );
+
+    let mut levels = Levels::from([(0, grid & !(1 << CENTER))]);
+    for _ in 0..minutes {
+        levels = step_recursive(&levels);
+    }
+    let bugs: u32 = levels.values().map(|grid| grid.count_ones()).sum();
+    println!(
+        "(2) After {} minutes, there are {} bugs across {} levels",
+        minutes,
+        bugs,
+        levels.len()
+    );
+
+    for depth in &args[1.min(args.len())..] {
+        print_level(&levels, *depth);
+    }
+}
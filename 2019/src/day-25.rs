@@ -0,0 +1,324 @@
+use itertools::Itertools;
+use std::collections::{HashSet, VecDeque};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+
+const BLACKLIST: [&str; 5] = [
+    "escape pod",
+    "giant electromagnet",
+    "infinite loop",
+    "molten lava",
+    "photons",
+];
+const CHECKPOINT: &str = "Security Checkpoint";
+
+struct Intcode {
+    memory: Vec<i64>,
+    ip: usize,
+    rel_base: i64,
+    input: VecDeque<i64>,
+    output: Vec<i64>,
+}
+
+impl Intcode {
+    fn new(memory: &[i64]) -> Self {
+        Intcode {
+            memory: memory.to_vec(),
+            ip: 0,
+            rel_base: 0,
+            input: VecDeque::new(),
+            output: vec![],
+        }
+    }
+
+    fn addr(&mut self, offset: usize) -> usize {
+        let mode = match offset {
+            1 => self.memory[self.ip] / 100 % 10,
+            2 => self.memory[self.ip] / 1000 % 10,
+            _ => self.memory[self.ip] / 10000 % 10,
+        };
+        let addr = match mode {
+            0 => self.read(self.ip + offset) as usize,
+            1 => self.ip + offset,
+            2 => (self.rel_base + self.read(self.ip + offset)) as usize,
+            _ => panic!("Unsupported mode {}", mode),
+        };
+        if addr >= self.memory.len() {
+            self.memory.resize(addr + 1, 0);
+        }
+        addr
+    }
+
+    fn read(&self, addr: usize) -> i64 {
+        self.memory.get(addr).copied().unwrap_or(0)
+    }
+
+    fn param(&mut self, offset: usize) -> i64 {
+        let addr = self.addr(offset);
+        self.memory[addr]
+    }
+
+    // Runs until the program halts (false) or is waiting for more input (true)
+    fn run(&mut self) -> bool {
+        loop {
+            match self.memory[self.ip] % 100 {
+                1 => {
+                    let dest = self.addr(3);
+                    self.memory[dest] = self.param(1) + self.param(2);
+                    self.ip += 4;
+                }
+                2 => {
+                    let dest = self.addr(3);
+                    self.memory[dest] = self.param(1) * self.param(2);
+                    self.ip += 4;
+                }
+                3 => {
+                    if let Some(value) = self.input.pop_front() {
+                        let dest = self.addr(1);
+                        self.memory[dest] = value;
+                        self.ip += 2;
+                    } else {
+                        return true;
+                    }
+                }
+                4 => {
+                    let value = self.param(1);
+                    self.output.push(value);
+                    self.ip += 2;
+                }
+                5 => {
+                    self.ip = if self.param(1) != 0 {
+                        self.param(2) as usize
+                    } else {
+                        self.ip + 3
+                    };
+                }
+                6 => {
+                    self.ip = if self.param(1) == 0 {
+                        self.param(2) as usize
+                    } else {
+                        self.ip + 3
+                    };
+                }
+                7 => {
+                    let dest = self.addr(3);
+                    self.memory[dest] = (self.param(1) < self.param(2)) as i64;
+                    self.ip += 4;
+                }
+                8 => {
+                    let dest = self.addr(3);
+                    self.memory[dest] = (self.param(1) == self.param(2)) as i64;
+                    self.ip += 4;
+                }
+                9 => {
+                    self.rel_base += self.param(1);
+                    self.ip += 2;
+                }
+                99 => return false,
+                _ => panic!("Unexpected opcode {}", self.memory[self.ip]),
+            }
+        }
+    }
+
+    fn take_output(&mut self) -> String {
+        self.output.drain(..).map(|c| c as u8 as char).collect()
+    }
+
+    fn send(&mut self, command: &str) -> String {
+        self.input
+            .extend(command.bytes().chain("\n".bytes()).map(|c| c as i64));
+        self.run();
+        self.take_output()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Room {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
+}
+
+fn parse_room(output: &str) -> Room {
+    let mut room = Room::default();
+    let mut section = "";
+    // When ejected from the pressure plate, only the last room description matters
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("== ").and_then(|l| l.strip_suffix(" ==")) {
+            room = Room {
+                name: name.to_string(),
+                ..Default::default()
+            };
+        } else if let Some(entry) = line.strip_prefix("- ") {
+            match section {
+                "Doors here lead:" => room.doors.push(entry.to_string()),
+                "Items here:" => room.items.push(entry.to_string()),
+                _ => {}
+            }
+        } else {
+            section = line;
+        }
+    }
+    room
+}
+
+fn opposite(direction: &str) -> &'static str {
+    match direction {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => panic!("Unexpected direction {}", direction),
+    }
+}
+
+struct Explorer {
+    visited: HashSet<String>,
+    inventory: Vec<String>,
+    path: Vec<String>,
+    checkpoint: Option<(Vec<String>, String)>,
+}
+
+impl Explorer {
+    fn explore(&mut self, droid: &mut Intcode, room: Room) {
+        self.visited.insert(room.name.clone());
+        for item in room.items {
+            if !BLACKLIST.contains(&item.as_str()) {
+                droid.send(&format!("take {}", item));
+                self.inventory.push(item);
+            }
+        }
+
+        for door in room.doors {
+            if room.name == CHECKPOINT {
+                if self.path.last().map(|d| opposite(d)) != Some(door.as_str()) {
+                    self.checkpoint = Some((self.path.clone(), door));
+                }
+                continue;
+            }
+
+            let next_room = parse_room(&droid.send(&door));
+            if self.visited.contains(&next_room.name) {
+                droid.send(opposite(&door));
+                continue;
+            }
+
+            self.path.push(door.clone());
+            self.explore(droid, next_room);
+            self.path.pop();
+            droid.send(opposite(&door));
+        }
+    }
+}
+
+fn find_password(droid: &mut Intcode, items: &[String], direction: &str) -> Option<String> {
+    for item in items {
+        droid.send(&format!("drop {}", item));
+    }
+
+    let mut too_heavy: Vec<HashSet<&String>> = vec![];
+    let mut too_light: Vec<HashSet<&String>> = vec![];
+    let mut carrying: HashSet<&String> = HashSet::new();
+    for subset in (0..=items.len()).flat_map(|k| items.iter().combinations(k)) {
+        let subset: HashSet<&String> = subset.into_iter().collect();
+        if too_heavy.iter().any(|s| s.is_subset(&subset))
+            || too_light.iter().any(|s| s.is_superset(&subset))
+        {
+            continue;
+        }
+
+        for item in carrying.difference(&subset) {
+            droid.send(&format!("drop {}", item));
+        }
+        for item in subset.difference(&carrying) {
+            droid.send(&format!("take {}", item));
+        }
+        carrying = subset;
+
+        let output = droid.send(direction);
+        let description = carrying.iter().sorted().join(", ");
+        if output.contains("heavier") {
+            println!("Tried [{}] => too light", description);
+            too_light.push(carrying.clone());
+        } else if output.contains("lighter") {
+            println!("Tried [{}] => too heavy", description);
+            too_heavy.push(carrying.clone());
+        } else {
+            println!("Tried [{}] => accepted", description);
+            return output
+                .split_whitespace()
+                .find(|word| word.chars().all(|c| c.is_ascii_digit()))
+                .map(|word| word.to_string());
+        }
+    }
+    None
+}
+
+fn play(memory: &[i64]) -> io::Result<()> {
+    let mut droid = Intcode::new(memory);
+    let mut stdin = io::stdin().lock();
+    while droid.run() {
+        print!("{}", droid.take_output());
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        droid
+            .input
+            .extend(line.trim_end().bytes().map(|c| c as i64));
+        droid.input.push_back('\n' as i64);
+    }
+    print!("{}", droid.take_output());
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if let [_, flag, path] = &args[..] {
+        if flag == "--play" {
+            let memory: Vec<i64> = fs::read_to_string(path)?
+                .trim()
+                .split(',')
+                .flat_map(str::parse)
+                .collect();
+            return play(&memory);
+        }
+    }
+
+    let mut line = String::new();
+    io::stdin().lock().read_to_string(&mut line)?;
+    let memory: Vec<i64> = line.trim().split(',').flat_map(str::parse).collect();
+
+    let mut droid = Intcode::new(&memory);
+    droid.run();
+    let start = parse_room(&droid.take_output());
+
+    let mut explorer = Explorer {
+        visited: HashSet::new(),
+        inventory: vec![],
+        path: vec![],
+        checkpoint: None,
+    };
+    explorer.explore(&mut droid, start);
+    println!(
+        "Explored {} rooms, collected {}",
+        explorer.visited.len(),
+        explorer.inventory.join(", ")
+    );
+
+    let (path, direction) = explorer.checkpoint.expect("Security checkpoint not found");
+    for door in &path {
+        droid.send(door);
+    }
+
+    if let Some(password) = find_password(&mut droid, &explorer.inventory, &direction) {
+        println!("(1) The password for the main airlock is {}", password);
+    } else {
+        println!("(1) No combination of items was accepted by the pressure-sensitive floor");
+    }
+
+    Ok(())
+}
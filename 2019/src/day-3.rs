@@ -1,77 +1,181 @@
-use std::collections::HashMap;
+use itertools::Itertools;
+use std::env;
 use std::io::{self, BufRead};
 
-type Grid = HashMap<Point, u32>;
 type Point = (i32, i32);
 
-fn walk_x(grid: &mut Grid, idx: u32, start_pos: Point, distance: i32) -> Point {
-    (1..=distance.abs())
-        .map(|dx| dx * distance.signum())
-        .for_each(|dx| {
-            let pos = (start_pos.0 + dx, start_pos.1);
-            grid.entry(pos).or_insert(idx + dx.abs() as u32);
-        });
-    (start_pos.0 + distance, start_pos.1)
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    start: Point,
+    end: Point,
+    // Number of steps the wire took before reaching the start of this segment
+    delay: u32,
 }
 
-fn walk_y(grid: &mut Grid, idx: u32, start_pos: Point, distance: i32) -> Point {
-    (1..=distance.abs())
-        .map(|dy| dy * distance.signum())
-        .for_each(|dy| {
-            let pos = (start_pos.0, start_pos.1 + dy);
-            grid.entry(pos).or_insert(idx + dy.abs() as u32);
-        });
-    (start_pos.0, start_pos.1 + distance)
+type Wire = Vec<Segment>;
+
+impl Segment {
+    fn min(&self) -> Point {
+        (self.start.0.min(self.end.0), self.start.1.min(self.end.1))
+    }
+
+    fn max(&self) -> Point {
+        (self.start.0.max(self.end.0), self.start.1.max(self.end.1))
+    }
+
+    fn contains(&self, point: &Point) -> bool {
+        let (min, max) = (self.min(), self.max());
+        (min.0..=max.0).contains(&point.0) && (min.1..=max.1).contains(&point.1)
+    }
+
+    // Part of the segment that lies inside an area, if any
+    fn clip(&self, area: &Area) -> Option<Area> {
+        let (min, max) = (self.min(), self.max());
+        let low = (min.0.max(area.0 .0), min.1.max(area.0 .1));
+        let high = (max.0.min(area.1 .0), max.1.min(area.1 .1));
+        if low.0 > high.0 || low.1 > high.1 {
+            None
+        } else {
+            Some((low, high))
+        }
+    }
 }
 
-fn manhattan_distance(point: &Point) -> i32 {
-    point.0.abs() + point.1.abs()
+// Lowest and highest corner of a part of the grid that every wire goes through
+type Area = (Point, Point);
+
+fn area_contains(area: &Area, point: &Point) -> bool {
+    (area.0 .0..=area.1 .0).contains(&point.0) && (area.0 .1..=area.1 .1).contains(&point.1)
 }
 
-fn wire_distance(point: &Point, grids: &Vec<Grid>) -> u32 {
-    grids[0][point] + grids[1][point]
+// The crossing point of perpendicular segments, or the points of interest of
+// an overlap: both ends, and the ones closest to the origin. When the overlap
+// contains the origin, those are its neighbours on either side.
+fn points_of_interest(area: &Area) -> Vec<Point> {
+    let (low, high) = *area;
+    let closest = (0.clamp(low.0, high.0), 0.clamp(low.1, high.1));
+    let points = if closest == (0, 0) {
+        vec![low, high, (-1, 0), (1, 0), (0, -1), (0, 1)]
+    } else {
+        vec![low, high, closest]
+    };
+    points
+        .into_iter()
+        .filter(|point| *point != (0, 0) && area_contains(area, point))
+        .unique()
+        .collect()
 }
 
-fn main() -> io::Result<()> {
-    let mut grids: Vec<Grid> = vec![];
-    io::stdin().lock().lines().flatten().for_each(|line| {
-        let mut grid: Grid = HashMap::new();
-        let mut pos = (0, 0);
-        let mut idx = 0;
-        line.split(',').for_each(|step| {
+fn parse_wire(line: &str) -> Wire {
+    let mut position = (0, 0);
+    let mut delay = 0;
+    line.split(',')
+        .map(|step| {
             let distance: i32 = step[1..].parse().unwrap();
-            pos = match &step[..1] {
-                "L" => walk_x(&mut grid, idx, pos, -distance),
-                "R" => walk_x(&mut grid, idx, pos, distance),
-                "U" => walk_y(&mut grid, idx, pos, -distance),
-                "D" => walk_y(&mut grid, idx, pos, distance),
+            let end = match &step[..1] {
+                "L" => (position.0 - distance, position.1),
+                "R" => (position.0 + distance, position.1),
+                "U" => (position.0, position.1 - distance),
+                "D" => (position.0, position.1 + distance),
                 _ => panic!("Unexpected input {}", step),
             };
-            idx += distance as u32;
-        });
-        grids.push(grid);
-    });
-
-    // Intersect grids
-    let mut crossings: Vec<Point> = grids[0]
-        .keys()
-        .filter(|point| grids[1].contains_key(point))
-        .cloned()
+            let segment = Segment {
+                start: position,
+                end,
+                delay,
+            };
+            position = end;
+            delay += distance as u32;
+            segment
+        })
+        .collect()
+}
+
+fn manhattan_distance(point: &Point) -> i32 {
+    point.0.abs() + point.1.abs()
+}
+
+// Number of steps the wire takes to first reach the point, if it ever does
+fn wire_delay(wire: &Wire, point: &Point) -> Option<u32> {
+    wire.iter()
+        .filter(|segment| segment.contains(point))
+        .map(|segment| {
+            segment.delay
+                + manhattan_distance(&(point.0 - segment.start.0, point.1 - segment.start.1)) as u32
+        })
+        .min()
+}
+
+// Points where all given wires cross, along with their combined delay
+fn find_crossings(wires: &[&Wire]) -> Vec<(Point, u32)> {
+    let (first, rest) = match wires.split_first() {
+        Some(split) => split,
+        None => return vec![],
+    };
+    // Narrow down the parts of the first wire to where each other wire overlaps them
+    let mut areas: Vec<Area> = first
+        .iter()
+        .map(|segment| (segment.min(), segment.max()))
         .collect();
+    for wire in rest {
+        areas = areas
+            .iter()
+            .cartesian_product(wire.iter())
+            .flat_map(|(area, segment)| segment.clip(area))
+            .collect();
+    }
+
+    areas
+        .iter()
+        .flat_map(points_of_interest)
+        .unique()
+        .filter_map(|point| {
+            wires
+                .iter()
+                .map(|wire| wire_delay(wire, &point))
+                .sum::<Option<u32>>()
+                .map(|delay| (point, delay))
+        })
+        .collect()
+}
+
+fn main() {
+    let wires: Vec<Wire> = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_wire(&line))
+        .collect();
+
+    // Optionally only consider the crossings of a chosen pair of wires
+    let selected: Vec<&Wire> = match env::args()
+        .skip(1)
+        .flat_map(|arg| arg.parse::<usize>())
+        .collect::<Vec<_>>()[..]
+    {
+        [a, b] => vec![&wires[a], &wires[b]],
+        _ => wires.iter().collect(),
+    };
+
+    let crossings = find_crossings(&selected);
+    if let Some((point, _)) = crossings
+        .iter()
+        .min_by_key(|(point, _)| manhattan_distance(point))
+    {
+        println!(
+            "(1) The closest crossing on the grid is at {:?} = {}",
+            point,
+            manhattan_distance(point)
+        );
+    } else {
+        println!("(1) The wires never all cross each other");
+    }
 
-    crossings.sort_by_key(manhattan_distance);
-    println!(
-        "(1) The closest crossing on the grid is at {:?} = {}",
-        crossings[0],
-        manhattan_distance(&crossings[0])
-    );
-
-    crossings.sort_by_key(|point| wire_distance(point, &grids));
-    println!(
-        "(2) The closest crossing in wire distance is at {:?} = {}",
-        crossings[0],
-        wire_distance(&crossings[0], &grids)
-    );
-
-    Ok(())
+    if let Some((point, delay)) = crossings.iter().min_by_key(|(_, delay)| *delay) {
+        println!(
+            "(2) The closest crossing in wire distance is at {:?} = {}",
+            point, delay
+        );
+    }
 }
@@ -1,53 +1,176 @@
+use std::collections::HashMap;
+use std::env;
 use std::io::{self, BufRead};
 use std::ops::RangeInclusive;
 
-fn count_options(
-    base: u32,
-    range: RangeInclusive<u32>,
-    last_digit: u32,
-    has_double: bool,
-    allow_overruns: bool,
+#[derive(Debug, Clone)]
+struct Rules {
+    base: u64,
+    non_decreasing: bool,
+    forbidden_digits: Vec<u64>,
+    // At least one run of equal digits must have a length in this range
+    run_length: Option<RangeInclusive<u32>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    // None while we're still reading leading zeros
+    last_digit: Option<u64>,
     current_run: u32,
-) -> u32 {
-    if base == 0 {
-        return (has_double || current_run == 2) as u32;
+    has_run: bool,
+}
+
+const START: State = State {
+    last_digit: None,
+    current_run: 0,
+    has_run: false,
+};
+
+struct DigitDp {
+    rules: Rules,
+    memo: HashMap<(u32, State), u64>,
+}
+
+impl DigitDp {
+    fn new(rules: Rules) -> Self {
+        DigitDp {
+            rules,
+            memo: HashMap::new(),
+        }
+    }
+
+    fn is_valid_run(&self, length: u32) -> bool {
+        self.rules
+            .run_length
+            .as_ref()
+            .is_some_and(|range| range.contains(&length))
+    }
+
+    fn step(&self, state: State, digit: u64) -> Option<State> {
+        match state.last_digit {
+            None if digit == 0 => Some(state),
+            _ if self.rules.forbidden_digits.contains(&digit) => None,
+            Some(last) if self.rules.non_decreasing && digit < last => None,
+            Some(last) if digit == last => Some(State {
+                current_run: state.current_run + 1,
+                ..state
+            }),
+            _ => Some(State {
+                last_digit: Some(digit),
+                current_run: 1,
+                has_run: state.has_run || self.is_valid_run(state.current_run),
+            }),
+        }
+    }
+
+    fn accepts(&self, state: &State) -> bool {
+        state.last_digit.is_some()
+            && (self.rules.run_length.is_none()
+                || state.has_run
+                || self.is_valid_run(state.current_run))
+    }
+
+    // Number of ways to append `remaining` digits to a number in `state`
+    fn count_completions(&mut self, remaining: u32, state: State) -> u64 {
+        if remaining == 0 {
+            return self.accepts(&state) as u64;
+        }
+        if let Some(count) = self.memo.get(&(remaining, state)) {
+            return *count;
+        }
+        let next_states: Vec<State> = (0..self.rules.base)
+            .filter_map(|digit| self.step(state, digit))
+            .collect();
+        let count = next_states
+            .into_iter()
+            .map(|next| self.count_completions(remaining - 1, next))
+            .sum();
+        self.memo.insert((remaining, state), count);
+        count
     }
 
-    let (min_digit, min_remainder) = (range.start() / base, range.start() % base);
-    let (max_digit, max_remainder) = (range.end() / base, range.end() % base);
-
-    let next_base = base / 10;
-    (min_digit..=max_digit)
-        .map(|digit| {
-            let next_min = if digit == min_digit {
-                min_remainder.max(digit * next_base)
-            } else {
-                digit * next_base
-            };
-            let next_max = if digit == max_digit {
-                max_remainder
-            } else {
-                base - 1
-            };
-            let next_has_double = if allow_overruns {
-                has_double || current_run >= 2
-            } else {
-                has_double || (digit != last_digit && current_run == 2)
-            };
-            count_options(
-                next_base,
-                next_min..=next_max,
-                digit,
-                next_has_double,
-                allow_overruns,
-                if digit == last_digit {
-                    current_run + 1
-                } else {
-                    1
-                },
-            )
-        })
-        .sum()
+    fn digits(&self, mut n: u64) -> Vec<u64> {
+        let mut digits = vec![];
+        while n > 0 {
+            digits.push(n % self.rules.base);
+            n /= self.rules.base;
+        }
+        digits.reverse();
+        digits
+    }
+
+    // Counts the matching numbers in 1..=n
+    fn count_up_to(&mut self, n: u64) -> u64 {
+        let digits = self.digits(n);
+        let mut count = 0;
+        let mut state = Some(START);
+        for (idx, &limit) in digits.iter().enumerate() {
+            let remaining = (digits.len() - idx - 1) as u32;
+            let Some(curr) = state else { break };
+            for digit in 0..limit {
+                if let Some(next) = self.step(curr, digit) {
+                    count += self.count_completions(remaining, next);
+                }
+            }
+            state = self.step(curr, limit);
+        }
+        count + state.map_or(0, |state| self.accepts(&state) as u64)
+    }
+
+    fn count(&mut self, range: &RangeInclusive<u64>) -> u64 {
+        if range.is_empty() {
+            return 0;
+        }
+        self.count_up_to(*range.end()) - self.count_up_to(range.start().saturating_sub(1))
+    }
+
+    fn matches(self, range: RangeInclusive<u64>) -> Matches {
+        let length = self.digits(*range.end()).len() as u32;
+        Matches {
+            dp: self,
+            range,
+            stack: vec![(0, length, START)],
+        }
+    }
+}
+
+// Yields the matching numbers in increasing order, skipping every subtree of
+// digits that contains no match
+struct Matches {
+    dp: DigitDp,
+    range: RangeInclusive<u64>,
+    stack: Vec<(u64, u32, State)>,
+}
+
+impl Iterator for Matches {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        while let Some((prefix, remaining, state)) = self.stack.pop() {
+            let size = self.dp.rules.base.pow(remaining);
+            let (low, high) = (prefix * size, prefix * size + size - 1);
+            if high < *self.range.start() || low > *self.range.end() {
+                continue;
+            }
+            if remaining == 0 {
+                if self.dp.accepts(&state) {
+                    return Some(prefix);
+                }
+                continue;
+            }
+            let is_inside = self.range.contains(&low) && self.range.contains(&high);
+            if is_inside && self.dp.count_completions(remaining, state) == 0 {
+                continue;
+            }
+            for digit in (0..self.dp.rules.base).rev() {
+                if let Some(next) = self.dp.step(state, digit) {
+                    self.stack
+                        .push((prefix * self.dp.rules.base + digit, remaining - 1, next));
+                }
+            }
+        }
+        None
+    }
 }
 
 fn main() -> io::Result<()> {
@@ -55,19 +178,38 @@ fn main() -> io::Result<()> {
     io::stdin().lock().read_line(&mut line)?;
 
     if let [lower, upper] = line
+        .trim()
         .split('-')
         .flat_map(|n| n.parse())
-        .collect::<Vec<u32>>()[..]
+        .collect::<Vec<u64>>()[..]
     {
-        let base = (10 as u32).pow((line.len() / 2 - 1) as u32);
+        let at_least_double = Rules {
+            base: 10,
+            non_decreasing: true,
+            forbidden_digits: vec![],
+            run_length: Some(2..=u32::MAX),
+        };
+        let exact_double = Rules {
+            run_length: Some(2..=2),
+            ..at_least_double.clone()
+        };
+
         println!(
             "(1) There are {} password options",
-            count_options(base, lower..=upper, u32::MAX, false, true, 0)
+            DigitDp::new(at_least_double).count(&(lower..=upper))
         );
+
+        let mut dp = DigitDp::new(exact_double);
         println!(
             "(2) There are {} password options when not allowing for longer runs",
-            count_options(base, lower..=upper, u32::MAX, false, false, 0)
+            dp.count(&(lower..=upper))
         );
+
+        if env::args().any(|arg| arg == "--list") {
+            for password in dp.matches(lower..=upper) {
+                println!("{}", password);
+            }
+        }
     }
 
     Ok(())
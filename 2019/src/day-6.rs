@@ -1,72 +1,214 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fmt;
+use std::fs;
 use std::io::{self, BufRead};
+use std::process;
 
-type OrbiterMap = HashMap<String, String>;
-type OrbitMap = HashMap<String, Vec<String>>;
-
-fn count_orbits(map: &OrbitMap, orbitee: &str, depth: usize) -> usize {
-    depth
-        + map
-            .get(orbitee)
-            .map(|orbitees| {
-                orbitees
-                    .iter()
-                    .map(|orbiter| count_orbits(map, orbiter, depth + 1))
-                    .sum()
-            })
-            .unwrap_or(0)
+#[derive(Debug)]
+enum OrbitError {
+    MalformedLine(String),
+    MultipleParents(String),
+    Cycle(String),
+    UnknownObject(String),
+}
+
+impl fmt::Display for OrbitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrbitError::MalformedLine(line) => write!(f, "Malformed orbit \"{}\"", line),
+            OrbitError::MultipleParents(name) => write!(f, "{} orbits more than one object", name),
+            OrbitError::Cycle(name) => write!(f, "{} is part of an orbit cycle", name),
+            OrbitError::UnknownObject(name) => write!(f, "Unknown object {}", name),
+        }
+    }
 }
 
-fn lca_path_size(map: &OrbiterMap, a: &str, b: &str) -> usize {
-    let mut a_parents = vec![a];
-    let mut parent = a;
-    while let Some(node) = map.get(parent) {
-        a_parents.push(node);
-        parent = node;
+struct OrbitTree {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    children: Vec<Vec<usize>>,
+    depth: Vec<usize>,
+    // ancestors[k][node] is the 2^k-th parent of node, or the root itself
+    ancestors: Vec<Vec<usize>>,
+}
+
+impl OrbitTree {
+    fn parse(lines: &[String]) -> Result<Self, OrbitError> {
+        let mut names: Vec<String> = vec![];
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        let mut intern = |name: &str| {
+            *ids.entry(name.to_string()).or_insert_with(|| {
+                names.push(name.to_string());
+                names.len() - 1
+            })
+        };
+
+        let mut edges = vec![];
+        for line in lines {
+            if let [orbitee, orbiter] = line.split(')').collect::<Vec<&str>>()[..] {
+                edges.push((intern(orbitee), intern(orbiter)));
+            } else {
+                return Err(OrbitError::MalformedLine(line.to_string()));
+            }
+        }
+
+        let mut parent: Vec<Option<usize>> = vec![None; names.len()];
+        let mut children = vec![vec![]; names.len()];
+        for (orbitee, orbiter) in edges {
+            if parent[orbiter].replace(orbitee).is_some() {
+                return Err(OrbitError::MultipleParents(names[orbiter].clone()));
+            }
+            children[orbitee].push(orbiter);
+        }
+
+        // Every object must be reachable from an object that orbits nothing
+        let mut depth = vec![usize::MAX; names.len()];
+        let mut queue: VecDeque<usize> =
+            (0..names.len()).filter(|&n| parent[n].is_none()).collect();
+        for &root in &queue {
+            depth[root] = 0;
+        }
+        while let Some(node) = queue.pop_front() {
+            for &child in &children[node] {
+                depth[child] = depth[node] + 1;
+                queue.push_back(child);
+            }
+        }
+        if let Some(node) = depth.iter().position(|&d| d == usize::MAX) {
+            return Err(OrbitError::Cycle(names[node].clone()));
+        }
+
+        let mut ancestors = vec![(0..names.len())
+            .map(|n| parent[n].unwrap_or(n))
+            .collect::<Vec<_>>()];
+        while 1 << ancestors.len() <= names.len() {
+            let last = ancestors.last().unwrap();
+            ancestors.push(last.iter().map(|&n| last[n]).collect());
+        }
+
+        Ok(OrbitTree {
+            names,
+            ids,
+            children,
+            depth,
+            ancestors,
+        })
+    }
+
+    fn id(&self, name: &str) -> Result<usize, OrbitError> {
+        self.ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| OrbitError::UnknownObject(name.to_string()))
     }
 
-    let mut b_parents = vec![b];
-    let mut parent = b;
-    while let Some(node) = map.get(parent) {
-        b_parents.push(node);
-        parent = node;
+    fn count_orbits(&self) -> usize {
+        self.depth.iter().sum()
     }
 
-    let lca_position = a_parents
-        .iter()
-        .rev()
-        .zip(b_parents.iter().rev())
-        .position(|(a, b)| a != b)
-        .unwrap();
-    let depth_in_a = a_parents.len() - lca_position;
-    let depth_in_b = b_parents
-        .iter()
-        .position(|node| *node == a_parents[depth_in_a])
-        .unwrap();
-    depth_in_a + depth_in_b - 1
+    fn lift(&self, mut node: usize, steps: usize) -> usize {
+        for (k, level) in self.ancestors.iter().enumerate() {
+            if steps & (1 << k) != 0 {
+                node = level[node];
+            }
+        }
+        node
+    }
+
+    fn lca(&self, a: usize, b: usize) -> Option<usize> {
+        let (mut a, mut b) = if self.depth[a] >= self.depth[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        a = self.lift(a, self.depth[a] - self.depth[b]);
+        if a == b {
+            return Some(a);
+        }
+        for level in self.ancestors.iter().rev() {
+            if level[a] != level[b] {
+                a = level[a];
+                b = level[b];
+            }
+        }
+        // Objects in separate trees have no common ancestor
+        (self.ancestors[0][a] == self.ancestors[0][b] && a != self.ancestors[0][a])
+            .then(|| self.ancestors[0][a])
+    }
+
+    // Number of orbits between two objects, if they are connected
+    fn distance(&self, a: &str, b: &str) -> Result<Option<usize>, OrbitError> {
+        let (a, b) = (self.id(a)?, self.id(b)?);
+        Ok(self
+            .lca(a, b)
+            .map(|lca| self.depth[a] + self.depth[b] - 2 * self.depth[lca]))
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph orbits {\n");
+        for (node, children) in self.children.iter().enumerate() {
+            for &child in children {
+                dot += &format!("  \"{}\" -> \"{}\";\n", self.names[child], self.names[node]);
+            }
+        }
+        dot + "}\n"
+    }
 }
 
 fn main() -> io::Result<()> {
-    let mut orbit_map: OrbitMap = HashMap::new();
-    let mut orbiter_map: OrbiterMap = HashMap::new();
-    io::stdin().lock().lines().flatten().for_each(|line| {
-        if let [orbitee, orbiter] = line.split(')').collect::<Vec<&str>>()[..] {
-            orbit_map
-                .entry(orbitee.to_owned())
-                .or_default()
-                .push(orbiter.to_owned());
-            orbiter_map.insert(orbiter.to_owned(), orbitee.to_owned());
+    let args: Vec<String> = env::args().collect();
+    let option = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|idx| args.get(idx + 1))
+    };
+
+    let lines: Vec<String> = io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let tree = match OrbitTree::parse(&lines) {
+        Ok(tree) => tree,
+        Err(err) => {
+            eprintln!("Invalid orbit map: {}", err);
+            process::exit(1);
         }
-    });
+    };
 
     println!(
         "(1) There are {} direct and indirect orbits",
-        count_orbits(&orbit_map, "COM", 0)
-    );
-    println!(
-        "(2) It takes {} orbit transfers to get YOU to SAN's orbiter",
-        lca_path_size(&orbiter_map, "YOU", "SAN") - 1,
+        tree.count_orbits()
     );
+    // Transfers are counted between the objects YOU and SAN orbit, so there is
+    // nothing to count when one of them orbits the other
+    match tree.distance("YOU", "SAN") {
+        Ok(Some(distance)) if distance >= 2 => println!(
+            "(2) It takes {} orbit transfers to get YOU to SAN's orbiter",
+            distance - 2,
+        ),
+        Ok(Some(_)) => println!("(2) YOU and SAN orbit each other directly"),
+        _ => {}
+    }
+
+    if let Some(path) = option("--queries") {
+        for query in fs::read_to_string(path)?.lines().filter(|l| !l.is_empty()) {
+            if let [a, b] = query.split_whitespace().collect::<Vec<&str>>()[..] {
+                match tree.distance(a, b) {
+                    Ok(Some(distance)) => println!("{} -> {}: {} orbits", a, b, distance),
+                    Ok(None) => println!("{} -> {}: not connected", a, b),
+                    Err(err) => println!("{} -> {}: {}", a, b, err),
+                }
+            }
+        }
+    }
+
+    if let Some(path) = option("--dot") {
+        fs::write(path, tree.to_dot())?;
+        println!("Exported the orbit tree to {}", path);
+    }
 
     Ok(())
 }
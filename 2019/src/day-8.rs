@@ -1,50 +1,178 @@
 use itertools::Itertools;
+use std::env;
+use std::fs;
 use std::io::{self, BufRead};
 
+#[path = "../../common/ocr.rs"]
+mod ocr;
+
+const BLACK: u8 = 0;
+const WHITE: u8 = 1;
+const TRANSPARENT: u8 = 2;
+
+#[derive(Debug)]
+struct SpaceImage {
+    width: usize,
+    height: usize,
+    layers: Vec<Vec<u8>>,
+}
+
+impl SpaceImage {
+    fn decode(data: &str, width: usize, height: usize) -> Self {
+        let digits: Vec<u8> = data
+            .trim()
+            .chars()
+            .map(|c| c.to_digit(10).expect("Invalid pixel") as u8)
+            .collect();
+        assert!(
+            digits.len().is_multiple_of(width * height),
+            "Image data does not fit in {}x{} layers",
+            width,
+            height
+        );
+        SpaceImage {
+            width,
+            height,
+            layers: digits.chunks(width * height).map(|l| l.to_vec()).collect(),
+        }
+    }
+
+    // Builds a single-layer image from rows of white (true) and black pixels
+    fn encode(bitmap: &[Vec<bool>]) -> Self {
+        SpaceImage {
+            width: bitmap[0].len(),
+            height: bitmap.len(),
+            layers: vec![bitmap
+                .iter()
+                .flatten()
+                .map(|&white| if white { WHITE } else { BLACK })
+                .collect()],
+        }
+    }
+
+    fn to_sif(&self) -> String {
+        self.layers.iter().flatten().join("")
+    }
+
+    fn composite(&self) -> Vec<u8> {
+        (0..self.width * self.height)
+            .map(|idx| {
+                self.layers
+                    .iter()
+                    .map(|layer| layer[idx])
+                    .find(|&pixel| pixel != TRANSPARENT)
+                    .unwrap_or(TRANSPARENT)
+            })
+            .collect()
+    }
+
+    fn to_bitmap(&self) -> Vec<Vec<bool>> {
+        self.composite()
+            .chunks(self.width)
+            .map(|row| row.iter().map(|&pixel| pixel == WHITE).collect())
+            .collect()
+    }
+
+    fn to_pbm(&self) -> String {
+        // PBM uses 1 for black pixels and 0 for white ones
+        let rows = self
+            .to_bitmap()
+            .iter()
+            .map(|row| row.iter().map(|&white| !white as u8).join(" "))
+            .join("\n");
+        format!("P1\n{} {}\n{}\n", self.width, self.height, rows)
+    }
+
+    fn to_ppm(&self) -> String {
+        let rows = self
+            .composite()
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|&pixel| match pixel {
+                        BLACK => "0 0 0",
+                        WHITE => "255 255 255",
+                        _ => "255 0 255",
+                    })
+                    .join(" ")
+            })
+            .join("\n");
+        format!("P3\n{} {}\n255\n{}\n", self.width, self.height, rows)
+    }
+}
+
+fn count_pixels(layer: &[u8], value: u8) -> usize {
+    layer.iter().filter(|&&pixel| pixel == value).count()
+}
+
 fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let export_idx = args.iter().position(|arg| arg == "--export");
+    let export_path = export_idx.and_then(|idx| args.get(idx + 1).cloned());
+    // The export path may be numeric, so it must not be read as a dimension
+    let dimensions: Vec<usize> = args
+        .iter()
+        .enumerate()
+        .filter(|&(idx, _)| export_idx.is_none_or(|export_idx| idx != export_idx + 1))
+        .flat_map(|(_, arg)| arg.parse())
+        .collect();
+    let (width, height) = match dimensions[..] {
+        [width, height] => (width, height),
+        _ => (25, 6),
+    };
+
+    if args.iter().any(|arg| arg == "--encode") {
+        let bitmap: Vec<Vec<bool>> = io::stdin()
+            .lock()
+            .lines()
+            .map_while(Result::ok)
+            .filter(|line| !line.is_empty())
+            .map(|line| line.chars().map(|c| c != ' ' && c != '.').collect())
+            .collect();
+        println!("{}", SpaceImage::encode(&bitmap).to_sif());
+        return Ok(());
+    }
+
     let mut line = String::new();
     io::stdin().lock().read_line(&mut line)?;
+    let image = SpaceImage::decode(&line, width, height);
 
-    let layers: Vec<String> = line
-        .chars()
-        .chunks(25 * 6)
-        .into_iter()
-        .map(|chars| chars.collect())
-        .collect();
-
-    let layer_with_fewest_0 = layers
+    let layer_with_fewest_0 = image
+        .layers
         .iter()
-        .map(|layer| layer.chars().filter(|c| c == &'0').count())
-        .enumerate()
-        .min_by(|(_, a), (_, b)| a.cmp(b))
-        .map(|(idx, _)| &layers[idx])
+        .min_by_key(|layer| count_pixels(layer, BLACK))
         .unwrap();
-
-    let result = layer_with_fewest_0.chars().filter(|c| c == &'1').count()
-        * layer_with_fewest_0.chars().filter(|c| c == &'2').count();
+    let result =
+        count_pixels(layer_with_fewest_0, WHITE) * count_pixels(layer_with_fewest_0, TRANSPARENT);
     println!(
         "(1) Layer with fewest 0 digits contains {} 1*2 digits",
         result
     );
 
-    let output = layers
-        .into_iter()
-        .rev()
-        .reduce(|prev_layer, curr_layer| {
-            curr_layer
-                .chars()
-                .zip(prev_layer.chars())
-                .map(|(curr, prev)| if curr == '2' { prev } else { curr })
-                .collect()
-        })
-        .unwrap()
-        .chars()
-        .map(|c| if c == '1' { '█' } else { ' ' })
-        .chunks(25)
-        .into_iter()
-        .map(|chunk| chunk.collect::<String>())
-        .join("\n");
-    println!("(2) The combined layer shows\n{}", output);
+    let bitmap = image.to_bitmap();
+    match ocr::recognize(&bitmap) {
+        Ok(message) => println!("(2) The combined layer shows {}", message),
+        Err(err) => {
+            let output = bitmap
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&white| if white { '█' } else { ' ' })
+                        .collect::<String>()
+                })
+                .join("\n");
+            println!("(2) The combined layer shows\n{}\n{}", output, err);
+        }
+    }
+
+    if let Some(path) = export_path {
+        if path.ends_with(".ppm") {
+            fs::write(&path, image.to_ppm())?;
+        } else {
+            fs::write(&path, image.to_pbm())?;
+        }
+        println!("Exported the combined layer to {}", path);
+    }
 
     Ok(())
 }
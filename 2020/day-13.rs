@@ -1,15 +1,17 @@
+use std::fmt;
 use std::io::{self, Read};
 
-fn find_first_bus(min_time: i64, bus_pattern: &Vec<Option<i64>>) -> (i64, i64) {
+fn find_first_bus(min_time: i64, bus_pattern: &[Option<i64>]) -> (i64, i64) {
     bus_pattern
         .iter()
         .filter_map(|b| *b)
-        .map(|b| (b, b - (min_time % b)))
+        .map(|b| (b, (b - (min_time % b)) % b))
         .min_by(|a, b| a.1.cmp(&b.1))
         .unwrap()
 }
 
-fn egcd(a: i64, b: i64) -> (i64, i64, i64) {
+// Returns (g, x, y) such that a * x + b * y = g = gcd(a, b)
+fn egcd(a: i128, b: i128) -> (i128, i128, i128) {
     if a == 0 {
         (b, 0, 1)
     } else {
@@ -18,31 +20,89 @@ fn egcd(a: i64, b: i64) -> (i64, i64, i64) {
     }
 }
 
-fn find_bus_sequence(bus_pattern: &Vec<Option<i64>>) -> i64 {
-    let schedule = bus_pattern
+#[derive(Debug)]
+enum CrtError {
+    NoSolution,
+    Overflow,
+}
+
+impl fmt::Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrtError::NoSolution => write!(f, "the congruences contradict each other"),
+            CrtError::Overflow => write!(f, "the combined modulus does not fit in 128 bits"),
+        }
+    }
+}
+
+// x ≡ residue (mod modulus), with 0 <= residue < modulus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Congruence {
+    residue: i128,
+    modulus: i128,
+}
+
+impl Congruence {
+    fn new(residue: i128, modulus: i128) -> Congruence {
+        Congruence {
+            residue: residue.rem_euclid(modulus),
+            modulus,
+        }
+    }
+
+    // Combines both congruences into a single one modulo their lcm, which
+    // exists only when the residues agree modulo the gcd of the moduli
+    fn merge(self, other: Congruence) -> Result<Congruence, CrtError> {
+        let (g, x, _) = egcd(self.modulus, other.modulus);
+        let diff = other.residue - self.residue;
+        if diff % g != 0 {
+            return Err(CrtError::NoSolution);
+        }
+        let step = other.modulus / g;
+        // x is the inverse of self.modulus / g modulo step
+        let k = (diff / g)
+            .rem_euclid(step)
+            .checked_mul(x.rem_euclid(step))
+            .ok_or(CrtError::Overflow)?
+            % step;
+        let modulus = self.modulus.checked_mul(step).ok_or(CrtError::Overflow)?;
+        let residue = self
+            .modulus
+            .checked_mul(k)
+            .and_then(|offset| offset.checked_add(self.residue))
+            .ok_or(CrtError::Overflow)?;
+        Ok(Congruence::new(residue, modulus))
+    }
+}
+
+fn crt(congruences: &[Congruence]) -> Result<Congruence, CrtError> {
+    congruences
         .iter()
-        .enumerate()
-        .filter_map(|(idx, bus)| bus.and_then(|bus| Some((idx, bus))));
-    let product = schedule.clone().map(|(_, b)| b).product::<i64>();
-    schedule
-        .map(|(idx, bus)| {
-            let a = (bus - idx as i64) % bus;
-            let m = product / bus;
-            let (_, x, _) = egcd(m, bus);
-            a * m * x
+        .try_fold(Congruence::new(0, 1), |acc, &congruence| {
+            acc.merge(congruence)
         })
-        .sum::<i64>()
-        % product
+}
+
+// Bus `id` at position `idx` has to leave at t + idx, so t ≡ -idx (mod id)
+fn find_bus_sequence(bus_pattern: &[Option<i64>]) -> Result<i128, CrtError> {
+    let congruences: Vec<Congruence> = bus_pattern
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, bus)| bus.map(|bus| Congruence::new(-(idx as i128), bus as i128)))
+        .collect();
+    crt(&congruences).map(|solution| solution.residue)
 }
 
 fn main() -> io::Result<()> {
     let mut input = String::new();
     io::stdin().lock().read_to_string(&mut input)?;
 
-    if let [min_time, buses] = input.split("\n").collect::<Vec<&str>>()[..] {
+    if let [min_time, buses] = input.lines().collect::<Vec<&str>>()[..] {
         let min_time: i64 = min_time.parse().unwrap();
-        let bus_pattern: Vec<Option<i64>> =
-            buses.split(",").map(|b| b.parse::<i64>().ok()).collect();
+        let bus_pattern: Vec<Option<i64>> = buses
+            .split(',')
+            .map(|b| b.parse::<i64>().ok().filter(|&b| b > 0))
+            .collect();
 
         let (next_bus, next_bus_ts) = find_first_bus(min_time, &bus_pattern);
         println!(
@@ -52,8 +112,10 @@ fn main() -> io::Result<()> {
             next_bus * next_bus_ts
         );
 
-        let ts = find_bus_sequence(&bus_pattern);
-        println!("(2) Bus pattern matches at {}", ts);
+        match find_bus_sequence(&bus_pattern) {
+            Ok(ts) => println!("(2) Bus pattern matches at {}", ts),
+            Err(err) => println!("(2) No solution: {}", err),
+        }
     }
 
     Ok(())
@@ -1,45 +1,105 @@
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::io::{self, BufRead};
 
+// Set of addresses: every floating bit can be either 0 or 1, the other bits
+// must equal the ones in `bits`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pattern {
+    bits: u64,
+    floating: u64,
+}
+
+impl Pattern {
+    fn new(bits: u64, floating: u64) -> Pattern {
+        Pattern {
+            bits: bits & !floating,
+            floating,
+        }
+    }
+
+    fn size(&self) -> u64 {
+        1 << self.floating.count_ones()
+    }
+
+    fn intersects(&self, other: &Pattern) -> bool {
+        (self.bits ^ other.bits) & !(self.floating | other.floating) == 0
+    }
+
+    // Splits the addresses of self that are not in other into disjoint
+    // patterns, one per bit that floats in self but is fixed in other
+    fn subtract(&self, other: &Pattern) -> Vec<Pattern> {
+        if !self.intersects(other) {
+            return vec![*self];
+        }
+        let mut remaining = *self;
+        let mut result = vec![];
+        let mut split_bits = self.floating & !other.floating;
+        while split_bits != 0 {
+            let bit = split_bits & split_bits.wrapping_neg();
+            split_bits &= !bit;
+            let floating = remaining.floating & !bit;
+            result.push(Pattern::new(remaining.bits | (!other.bits & bit), floating));
+            remaining = Pattern::new(remaining.bits | (other.bits & bit), floating);
+        }
+        result
+    }
+}
+
+// Disjoint patterns with the value stored at each of their addresses. A new
+// write carves its addresses out of the older patterns instead of expanding
+// the floating bits, so the work is independent of how many there are.
+struct Memory {
+    writes: Vec<(Pattern, u64)>,
+}
+
+impl Memory {
+    fn new() -> Memory {
+        Memory { writes: vec![] }
+    }
+
+    fn write(&mut self, pattern: Pattern, value: u64) {
+        let mut writes: Vec<(Pattern, u64)> = self
+            .writes
+            .iter()
+            .flat_map(|(older, value)| {
+                older
+                    .subtract(&pattern)
+                    .into_iter()
+                    .map(move |rest| (rest, *value))
+            })
+            .collect();
+        writes.push((pattern, value));
+        self.writes = writes;
+    }
+
+    fn sum(&self) -> u128 {
+        self.writes
+            .iter()
+            .map(|(pattern, value)| pattern.size() as u128 * *value as u128)
+            .sum()
+    }
+}
+
 fn main() -> io::Result<()> {
     let mut mask_or: u64 = 0;
     let mut mask_and: u64 = 0;
-    let mut floating_bits: Vec<usize> = Vec::new();
+    let mut mask_floating: u64 = 0;
 
     let mut memory: HashMap<u64, u64> = HashMap::new();
-    let mut floating_memory: HashMap<u64, u64> = HashMap::new();
+    let mut floating_memory = Memory::new();
 
     for line in io::stdin().lock().lines() {
         let line = line?;
-        if line.starts_with("mask") {
-            let mask = &line["mask = ".len()..];
-            mask_or = u64::from_str_radix(&mask.replace("X", "0"), 2).unwrap();
-            mask_and = u64::from_str_radix(&mask.replace("X", "1"), 2).unwrap();
-            floating_bits = mask
-                .chars()
-                .enumerate()
-                .filter(|(_, c)| *c == 'X')
-                .map(|(i, _)| mask.len() - i - 1)
-                .collect();
-        } else if line.starts_with("mem") {
-            if let [address, value] = &line["mem[".len()..].split("] = ").collect::<Vec<&str>>()[..]
-            {
+        if let Some(mask) = line.strip_prefix("mask = ") {
+            mask_or = u64::from_str_radix(&mask.replace('X', "0"), 2).unwrap();
+            mask_and = u64::from_str_radix(&mask.replace('X', "1"), 2).unwrap();
+            mask_floating = mask_and & !mask_or;
+        } else if let Some(write) = line.strip_prefix("mem[") {
+            if let [address, value] = write.split("] = ").collect::<Vec<&str>>()[..] {
                 let address: u64 = address.parse().unwrap();
                 let value: u64 = value.parse().unwrap();
                 memory.insert(address, (value | mask_or) & mask_and);
-
-                let mut floating_addresses: HashSet<u64> =
-                    vec![address | mask_or].into_iter().collect();
-                for pos in &floating_bits {
-                    for addr in floating_addresses.clone() {
-                        floating_addresses.insert(addr | (1_u64 << pos));
-                        floating_addresses.insert(addr & !(1_u64 << pos));
-                    }
-                }
-                for addr in floating_addresses {
-                    floating_memory.insert(addr, value);
-                }
+                floating_memory.write(Pattern::new(address | mask_or, mask_floating), value);
             }
         }
     }
@@ -47,7 +107,7 @@ fn main() -> io::Result<()> {
     let result: u64 = memory.values().sum();
     println!("(1) Sum of all values in memory is {}", result);
 
-    let result: u64 = floating_memory.values().sum();
+    let result = floating_memory.sum();
     println!("(2) Sum of all floating values in memory is {}", result);
 
     Ok(())
@@ -1,15 +1,33 @@
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::HashMap;
+use std::env;
 use std::io::{self, Read};
 use std::ops::RangeInclusive;
 
 type Rule = Vec<RangeInclusive<i32>>;
-type Rules = HashMap<String, Rule>;
+// Kept in input order, so that fields can be referred to by index
+type Rules = Vec<(String, Rule)>;
+
+#[derive(Debug)]
+struct Discarded {
+    // Position in the nearby tickets, starting at 1
+    ticket: usize,
+    invalid_values: Vec<i32>,
+}
+
+#[derive(Debug)]
+enum Resolution {
+    // Column of every field
+    Unique(Vec<usize>),
+    // Fields that could go in more than one column
+    Ambiguous(Vec<usize>),
+    // Fields left without a column by a maximum matching
+    Impossible(Vec<usize>),
+}
 
 fn parse_ticket(input: &str) -> Vec<i32> {
     input
-        .split(",")
+        .split(',')
         .flat_map(|n| n.parse::<i32>().ok())
         .collect()
 }
@@ -18,17 +36,17 @@ fn parse_rules(input: &str) -> Rules {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"(?m)^([\w\s]+): (\d+)-(\d+) or (\d+)-(\d+)$").unwrap();
     }
-    let mut result = HashMap::new();
+    let mut result = vec![];
     for captures in RE.captures_iter(input) {
         let numbers: Vec<i32> = captures
             .iter()
             .skip(2)
             .flat_map(|m| m.and_then(|n| n.as_str().parse().ok()))
             .collect();
-        result.insert(
+        result.push((
             captures[1].to_string(),
             vec![numbers[0]..=numbers[1], numbers[2]..=numbers[3]],
-        );
+        ));
     }
     result
 }
@@ -37,70 +55,187 @@ fn matches_rule(rule: &Rule, input: &i32) -> bool {
     rule.iter().any(|range| range.contains(input))
 }
 
-fn find_ordering(rules: &Rules, tickets: &Vec<Vec<i32>>, ordering: &mut Vec<String>) -> bool {
-    let current_idx = ordering.len();
-    if current_idx == rules.len() {
-        return true;
-    }
+// compatible[field][column] is set when every valid ticket has a value in
+// that column that the field's rule accepts
+fn compatibility(rules: &Rules, tickets: &[Vec<i32>], columns: usize) -> Vec<Vec<bool>> {
+    rules
+        .iter()
+        .map(|(_, rule)| {
+            (0..columns)
+                .map(|column| {
+                    tickets
+                        .iter()
+                        .all(|ticket| ticket.get(column).is_some_and(|n| matches_rule(rule, n)))
+                })
+                .collect()
+        })
+        .collect()
+}
 
-    for (rule_name, rule) in rules {
-        if ordering.contains(rule_name) {
+// Kuhn's augmenting path search: tries to give `field` a column, moving
+// already matched fields to other columns where needed
+fn augment(
+    compatible: &[Vec<bool>],
+    banned: Option<(usize, usize)>,
+    field: usize,
+    visited: &mut Vec<bool>,
+    owner: &mut Vec<Option<usize>>,
+) -> bool {
+    for column in 0..owner.len() {
+        if !compatible[field][column] || visited[column] || banned == Some((field, column)) {
             continue;
         }
-
-        let is_valid = tickets
-            .iter()
-            .all(|ticket| matches_rule(rule, &ticket[current_idx]));
-        if is_valid {
-            ordering.push(rule_name.clone());
-            if find_ordering(rules, tickets, ordering) {
-                return true;
-            }
-            ordering.pop();
+        visited[column] = true;
+        let is_free = match owner[column] {
+            None => true,
+            Some(other) => augment(compatible, banned, other, visited, owner),
+        };
+        if is_free {
+            owner[column] = Some(field);
+            return true;
         }
     }
     false
 }
 
+// Returns the column of each field in a maximum matching
+fn max_matching(compatible: &[Vec<bool>], banned: Option<(usize, usize)>) -> Vec<Option<usize>> {
+    let columns = compatible.first().map_or(0, |row| row.len());
+    let mut owner: Vec<Option<usize>> = vec![None; columns];
+    for field in 0..compatible.len() {
+        augment(
+            compatible,
+            banned,
+            field,
+            &mut vec![false; columns],
+            &mut owner,
+        );
+    }
+
+    let mut assignment = vec![None; compatible.len()];
+    for (column, field) in owner.iter().enumerate() {
+        if let Some(field) = field {
+            assignment[*field] = Some(column);
+        }
+    }
+    assignment
+}
+
+// A field is ambiguous when a complete assignment still exists after
+// forbidding the column it got
+fn resolve(compatible: &[Vec<bool>]) -> Resolution {
+    let matching = max_matching(compatible, None);
+    let unassigned: Vec<usize> = (0..matching.len())
+        .filter(|&field| matching[field].is_none())
+        .collect();
+    if !unassigned.is_empty() {
+        return Resolution::Impossible(unassigned);
+    }
+
+    let assignment: Vec<usize> = matching.into_iter().flatten().collect();
+    let ambiguous: Vec<usize> = (0..assignment.len())
+        .filter(|&field| {
+            max_matching(compatible, Some((field, assignment[field])))
+                .iter()
+                .all(Option::is_some)
+        })
+        .collect();
+    if ambiguous.is_empty() {
+        Resolution::Unique(assignment)
+    } else {
+        Resolution::Ambiguous(ambiguous)
+    }
+}
+
 fn main() -> io::Result<()> {
+    let explain = env::args().any(|arg| arg == "--explain");
+
     let mut input = String::new();
     io::stdin().lock().read_to_string(&mut input)?;
 
-    let mut rules: Rules = HashMap::new();
-    let mut error_rate: i32 = 0;
+    let mut rules: Rules = vec![];
     let mut my_ticket: Vec<i32> = Vec::new();
     let mut valid_tickets: Vec<Vec<i32>> = Vec::new();
+    let mut discarded: Vec<Discarded> = Vec::new();
 
     for section in input.split("\n\n") {
         if rules.is_empty() {
             rules = parse_rules(section);
         } else if section.starts_with("your ticket:") {
-            my_ticket = section.split("\n").nth(1).map(parse_ticket).unwrap();
+            my_ticket = section.lines().nth(1).map(parse_ticket).unwrap();
         } else if section.starts_with("nearby tickets:") {
-            for ticket in section.split("\n").skip(1).map(parse_ticket) {
-                let invalid_sum: i32 = ticket
+            for (idx, ticket) in section.lines().skip(1).map(parse_ticket).enumerate() {
+                let invalid_values: Vec<i32> = ticket
                     .iter()
-                    .filter(|n| !rules.values().any(|rule| matches_rule(rule, *n)))
-                    .sum();
-                if invalid_sum == 0 {
+                    .filter(|n| !rules.iter().any(|(_, rule)| matches_rule(rule, n)))
+                    .copied()
+                    .collect();
+                if invalid_values.is_empty() {
                     valid_tickets.push(ticket);
                 } else {
-                    error_rate += invalid_sum;
+                    discarded.push(Discarded {
+                        ticket: idx + 1,
+                        invalid_values,
+                    });
                 }
             }
         }
     }
-    println!("(1) Ticket scanning error rate = {}", error_rate);
 
-    let mut ordering = Vec::new();
-    find_ordering(&rules, &valid_tickets, &mut ordering);
-    let product: i64 = ordering
+    let error_rate: i32 = discarded
         .iter()
-        .enumerate()
-        .filter(|(_, rule)| rule.starts_with("departure"))
-        .map(|(idx, _)| my_ticket[idx] as i64)
-        .product();
-    println!("(2) Product of departure fields = {}", product);
+        .flat_map(|ticket| &ticket.invalid_values)
+        .sum();
+    println!("(1) Ticket scanning error rate = {}", error_rate);
+    println!(
+        "Discarded {} of {} nearby tickets",
+        discarded.len(),
+        discarded.len() + valid_tickets.len()
+    );
+    if explain {
+        for ticket in &discarded {
+            println!(
+                "  Ticket {}: {:?} match no field",
+                ticket.ticket, ticket.invalid_values
+            );
+        }
+    }
+
+    let compatible = compatibility(&rules, &valid_tickets, my_ticket.len());
+    match resolve(&compatible) {
+        Resolution::Unique(assignment) => {
+            if explain {
+                for (field, column) in assignment.iter().enumerate() {
+                    println!("  {} is column {}", rules[field].0, column);
+                }
+            }
+            let product: i64 = assignment
+                .iter()
+                .enumerate()
+                .filter(|(field, _)| rules[*field].0.starts_with("departure"))
+                .map(|(_, &column)| my_ticket[column] as i64)
+                .product();
+            println!("(2) Product of departure fields = {}", product);
+        }
+        Resolution::Ambiguous(ambiguous) => {
+            println!("(2) The fields cannot be told apart:");
+            for field in ambiguous {
+                let columns: Vec<usize> = (0..my_ticket.len())
+                    .filter(|&column| compatible[field][column])
+                    .collect();
+                println!("  {} fits columns {:?}", rules[field].0, columns);
+            }
+        }
+        Resolution::Impossible(unassigned) => {
+            println!("(2) No column is left for some fields:");
+            for field in unassigned {
+                let columns: Vec<usize> = (0..my_ticket.len())
+                    .filter(|&column| compatible[field][column])
+                    .collect();
+                println!("  {} fits columns {:?}", rules[field].0, columns);
+            }
+        }
+    }
 
     Ok(())
 }
@@ -1,166 +1,202 @@
 use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::io::{self, Read};
 use std::iter;
-use std::ops::Range;
 
-#[derive(Clone)]
-struct State {
-    dimensions: Vec<Range<i32>>,
-    data: Vec<bool>,
+const CYCLES: usize = 6;
+
+// Life-like rule in B/S notation, e.g. B3/S23, where the digits are the
+// neighbour counts that make a dead cell alive or keep a live cell alive
+#[derive(Debug, Clone)]
+struct Rule {
+    birth: Vec<usize>,
+    survival: Vec<usize>,
 }
 
-impl State {
-    fn new(input: Vec<Vec<bool>>) -> State {
-        State {
-            dimensions: vec![0..(input[0].len() as i32), 0..(input.len() as i32)],
-            data: input.into_iter().flatten().collect(),
+impl Rule {
+    fn parse(input: &str) -> Result<Rule, String> {
+        let mut rule = Rule {
+            birth: vec![],
+            survival: vec![],
+        };
+        for part in input.split('/') {
+            let mut chars = part.chars();
+            let counts = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => &mut rule.birth,
+                Some('S') => &mut rule.survival,
+                _ => return Err(format!("'{}' is neither a birth nor a survival part", part)),
+            };
+            for c in chars {
+                let count = c
+                    .to_digit(10)
+                    .ok_or_else(|| format!("'{}' is not a neighbour count", c))?;
+                counts.push(count as usize);
+            }
         }
-    }
-
-    fn new_with_state(prev_state: &State) -> State {
-        let extended_dimensions: Vec<Range<i32>> = prev_state
-            .get_minimal_dimensions()
-            .iter()
-            .map(|r| (r.start - 1)..(r.end + 1))
-            .collect();
-        let extended_len = extended_dimensions
-            .iter()
-            .map(|r| (r.end - r.start) as usize)
-            .product();
-        State {
-            dimensions: extended_dimensions,
-            data: vec![false; extended_len],
+        if rule.birth.contains(&0) {
+            // Every empty cell in an infinite space would come alive at once
+            return Err("B0 rules are not supported".to_string());
         }
+        Ok(rule)
     }
 
-    fn set_dimensionality(&mut self, dimensions: usize) {
-        self.dimensions.reserve(dimensions);
-        for _ in self.dimensions.len()..dimensions {
-            self.dimensions.push(0..1);
+    fn next(&self, alive: bool, neighbours: usize) -> bool {
+        if alive {
+            self.survival.contains(&neighbours)
+        } else {
+            self.birth.contains(&neighbours)
         }
     }
+}
 
-    fn get_minimal_dimensions(&self) -> Vec<Range<i32>> {
-        let mut min_max: Option<Vec<Range<i32>>> = None;
-        for point in self
-            .dimensions
-            .clone()
-            .into_iter()
-            .multi_cartesian_product()
-        {
-            if self.get(&point) {
-                if let Some(curr_min_max) = &min_max {
-                    min_max = Some(
-                        curr_min_max
-                            .iter()
-                            .zip(point)
-                            .map(|(range, c)| (range.start.min(c)..range.end.max(c + 1)))
-                            .collect(),
-                    );
-                } else {
-                    min_max = Some(point.iter().map(|&c| c..(c + 1)).collect());
+// Only the live cells are stored. The input is a 2D slice at 0 in every
+// higher dimension, so the state stays symmetric under mirroring and swapping
+// those dimensions: each stored cell then stands for its whole orbit, with
+// the higher coordinates made absolute and sorted.
+struct State {
+    // Number of leading dimensions that are not reduced by symmetry
+    planar: usize,
+    cells: HashSet<Vec<i32>>,
+    deltas: Vec<Vec<i32>>,
+}
+
+impl State {
+    fn new(input: &[Vec<bool>], dimensions: usize, symmetric: bool) -> State {
+        let mut cells = HashSet::new();
+        for (y, row) in input.iter().enumerate() {
+            for (x, &alive) in row.iter().enumerate() {
+                if alive {
+                    let mut point = vec![0; dimensions];
+                    point[0] = x as i32;
+                    point[1] = y as i32;
+                    cells.insert(point);
                 }
             }
         }
-        min_max.unwrap()
-    }
-
-    fn get_offset(&self, point: &Vec<i32>) -> usize {
-        assert!(point.len() == self.dimensions.len());
-        let mut multiplier = 1;
-        let mut offset = 0;
-        for i in 0..point.len() {
-            offset += (point[i] - self.dimensions[i].start) * multiplier;
-            multiplier *= self.dimensions[i].end - self.dimensions[i].start;
+        let deltas = iter::repeat_n(vec![-1, 0, 1], dimensions)
+            .multi_cartesian_product()
+            .filter(|delta| delta.iter().any(|&d| d != 0))
+            .collect();
+        State {
+            planar: if symmetric { 2 } else { dimensions },
+            cells,
+            deltas,
         }
-        offset as usize
     }
 
-    fn get(&self, point: &Vec<i32>) -> bool {
-        if point
-            .iter()
-            .enumerate()
-            .all(|(idx, c)| self.dimensions[idx].contains(c))
-        {
-            self.data[self.get_offset(point)]
-        } else {
-            false
+    fn canonical(&self, mut point: Vec<i32>) -> Vec<i32> {
+        let reduced = &mut point[self.planar..];
+        for c in reduced.iter_mut() {
+            *c = c.abs();
         }
+        reduced.sort_unstable();
+        point
     }
 
-    fn set(&mut self, point: &Vec<i32>, value: bool) -> () {
-        let offset = self.get_offset(point);
-        self.data[offset] = value;
+    // Number of cells in the orbit of a canonical point
+    fn multiplicity(&self, point: &[i32]) -> usize {
+        let reduced = &point[self.planar..];
+        let factorial = |n: usize| (1..=n).product::<usize>();
+        let permutations = reduced
+            .iter()
+            .group_by(|&&c| c)
+            .into_iter()
+            .fold(factorial(reduced.len()), |acc, (_, group)| {
+                acc / factorial(group.count())
+            });
+        let mirrors = 1 << reduced.iter().filter(|&&c| c != 0).count();
+        permutations * mirrors
     }
 
-    fn step(&self) -> State {
-        let mut next = State::new_with_state(&self);
-        for point in next
-            .dimensions
-            .clone()
-            .into_iter()
-            .multi_cartesian_product()
-        {
-            let neighbours = self.count_active_neighbours(&point);
-            if self.get(&point) {
-                next.set(&point, neighbours == 2 || neighbours == 3);
-            } else {
-                next.set(&point, neighbours == 3);
+    fn step(&self, rule: &Rule) -> State {
+        // Every stored cell adds its whole orbit to the counts of its
+        // neighbours, so the sums are divided by the size of the target's
+        // orbit to get the count for a single representative
+        let mut weighted: HashMap<Vec<i32>, usize> = HashMap::new();
+        for cell in &self.cells {
+            let weight = self.multiplicity(cell);
+            weighted.entry(cell.clone()).or_default();
+            for delta in &self.deltas {
+                let neighbour = cell.iter().zip(delta).map(|(a, b)| a + b).collect();
+                *weighted.entry(self.canonical(neighbour)).or_default() += weight;
             }
         }
-        next
-    }
 
-    fn count_active_neighbours(&self, point: &Vec<i32>) -> usize {
-        iter::repeat(vec![-1, 0, 1])
-            .take(point.len())
-            .multi_cartesian_product()
-            .map(|delta| {
-                if delta == vec![0; point.len()] {
-                    0
-                } else {
-                    let neighbour: Vec<i32> = point.iter().zip(delta).map(|(a, b)| a + b).collect();
-                    self.get(&neighbour) as usize
-                }
+        let cells = weighted
+            .into_iter()
+            .filter(|(point, sum)| {
+                let neighbours = sum / self.multiplicity(point);
+                rule.next(self.cells.contains(point), neighbours)
             })
-            .sum()
+            .map(|(point, _)| point)
+            .collect();
+        State {
+            planar: self.planar,
+            cells,
+            deltas: self.deltas.clone(),
+        }
     }
 
     fn count_active(&self) -> usize {
-        self.data.iter().map(|b| *b as usize).sum()
+        self.cells.iter().map(|cell| self.multiplicity(cell)).sum()
+    }
+}
+
+fn run(input: &[Vec<bool>], rule: &Rule, dimensions: usize, symmetric: bool) -> usize {
+    let mut state = State::new(input, dimensions, symmetric);
+    for _ in 0..CYCLES {
+        state = state.step(rule);
     }
+    state.count_active()
 }
 
 fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let rule = args
+        .iter()
+        .find(|arg| arg.starts_with(['B', 'b']))
+        .map_or("B3/S23", |arg| arg.as_str());
+    let rule = match Rule::parse(rule) {
+        Ok(rule) => rule,
+        Err(err) => {
+            eprintln!("Invalid rule {}: {}", rule, err);
+            return Ok(());
+        }
+    };
+    let symmetric = !args.iter().any(|arg| arg == "--no-symmetry");
+    let extra_dimensions: Vec<usize> = args
+        .iter()
+        .flat_map(|arg| arg.parse())
+        .filter(|&dimensions| dimensions >= 2)
+        .collect();
+
     let mut input = String::new();
     io::stdin().lock().read_to_string(&mut input)?;
+    let initial_state: Vec<Vec<bool>> = input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.chars().map(|c| c == '#').collect())
+        .collect();
 
-    let initial_state = State::new(
-        input
-            .split("\n")
-            .map(|l| l.chars().map(|c| c == '#').collect())
-            .collect(),
-    );
-
-    let mut state = initial_state.clone();
-    state.set_dimensionality(3);
-    for _ in 0..6 {
-        state = state.step()
-    }
     println!(
-        "(1) After 6 steps in 3 dimensions, there's {} cells",
-        state.count_active()
+        "(1) After {} steps in 3 dimensions, there's {} cells",
+        CYCLES,
+        run(&initial_state, &rule, 3, symmetric)
     );
-
-    let mut state = initial_state.clone();
-    state.set_dimensionality(4);
-    for _ in 0..6 {
-        state = state.step();
-    }
     println!(
-        "(2) After 6 steps in 4 dimensions, there's {} cells",
-        state.count_active()
+        "(2) After {} steps in 4 dimensions, there's {} cells",
+        CYCLES,
+        run(&initial_state, &rule, 4, symmetric)
     );
+    for dimensions in extra_dimensions {
+        println!(
+            "After {} steps in {} dimensions, there's {} cells",
+            CYCLES,
+            dimensions,
+            run(&initial_state, &rule, dimensions, symmetric)
+        );
+    }
 
     Ok(())
 }
@@ -1,82 +1,224 @@
+use std::convert::TryFrom;
+use std::fmt;
 use std::io::{self, BufRead};
 
-#[derive(Debug, PartialEq)]
-enum Entry {
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
     Num(i64),
     Op(char),
-    Parentheses,
+    Open,
+    Close,
 }
 
-fn perform_op(input: &[Entry]) -> Entry {
-    if let [Entry::Num(a), Entry::Op(c), Entry::Num(b)] = input {
-        Entry::Num(if *c == '*' { a * b } else { a + b })
-    } else {
-        unreachable!();
+#[derive(Debug)]
+enum Expr {
+    Num(i64),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Assoc {
+    Left,
+    Right,
+}
+
+#[derive(Debug)]
+enum Error {
+    UnexpectedChar(usize, char),
+    UnexpectedToken(usize, Token),
+    UnexpectedEnd,
+    UnknownOperator(usize, char),
+    DivisionByZero,
+    Overflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnexpectedChar(pos, c) => write!(f, "unexpected character '{}' at {}", c, pos),
+            Error::UnexpectedToken(pos, token) => write!(f, "unexpected {:?} at {}", token, pos),
+            Error::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            Error::UnknownOperator(pos, c) => write!(f, "unknown operator '{}' at {}", c, pos),
+            Error::DivisionByZero => write!(f, "division by zero"),
+            Error::Overflow => write!(f, "arithmetic overflow"),
+        }
     }
 }
 
-fn reduce_stack(stack: &mut Vec<Entry>, order_matters: bool) -> Entry {
-    let mut input: Vec<Entry>;
-    if let Some(parentheses_idx) = stack.iter().rposition(|e| e == &Entry::Parentheses) {
-        input = stack.drain(parentheses_idx..).skip(1).collect();
-    } else {
-        input = stack.drain(0..).collect();
+// Binding power and associativity of each binary operator, and the binding
+// power of unary minus
+struct Precedence {
+    binary: Vec<(char, u8, Assoc)>,
+    unary: u8,
+}
+
+impl Precedence {
+    fn binary(&self, op: char) -> Option<(u8, Assoc)> {
+        self.binary
+            .iter()
+            .find(|(c, _, _)| *c == op)
+            .map(|&(_, power, assoc)| (power, assoc))
     }
+}
 
-    if order_matters {
-        let mut idx = input.len() - 1;
-        while idx >= 2 && input.len() >= 3 {
-            if input[idx - 1] == Entry::Op('+') {
-                let result = perform_op(input.drain((idx - 2)..(idx + 1)).as_slice());
-                input.insert(idx - 2, result);
+fn tokenize(line: &str) -> Result<Vec<(usize, Token)>, Error> {
+    let mut tokens = vec![];
+    let chars: Vec<char> = line.chars().collect();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        match c {
+            _ if c.is_ascii_whitespace() => {}
+            '0'..='9' => {
+                let start = idx;
+                while idx + 1 < chars.len() && chars[idx + 1].is_ascii_digit() {
+                    idx += 1;
+                }
+                let num: String = chars[start..=idx].iter().collect();
+                let num = num.parse().map_err(|_| Error::Overflow)?;
+                tokens.push((start, Token::Num(num)));
             }
-            idx -= 2;
+            '+' | '-' | '*' | '/' | '^' => tokens.push((idx, Token::Op(c))),
+            '(' => tokens.push((idx, Token::Open)),
+            ')' => tokens.push((idx, Token::Close)),
+            _ => return Err(Error::UnexpectedChar(idx, c)),
         }
+        idx += 1;
     }
+    Ok(tokens)
+}
 
-    while input.len() >= 3 {
-        let result = perform_op(input.drain(0..3).as_slice());
-        input.insert(0, result);
-    }
-
-    assert!(input.len() == 1);
-    input.pop().unwrap()
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    pos: usize,
+    precedence: &'a Precedence,
 }
 
-fn main() -> io::Result<()> {
-    let mut sum: [i64; 2] = [0, 0];
-    for line in io::stdin().lock().lines() {
-        let line = line?;
-        for &order_matters in &[false, true] {
-            let mut pos = 0;
-            let mut stack: Vec<Entry> = Vec::new();
-            for (idx, c) in line.chars().enumerate() {
-                if c.is_ascii_whitespace() || c == ')' {
-                    if idx > pos {
-                        let num = line[pos..idx].parse().unwrap();
-                        stack.push(Entry::Num(num));
-                    }
-                } else if c.is_digit(10) {
-                    continue;
-                }
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<(usize, Token), Error> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .copied()
+            .ok_or(Error::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
 
-                if c == '*' || c == '+' {
-                    stack.push(Entry::Op(c));
-                } else if c == '(' {
-                    stack.push(Entry::Parentheses);
-                } else if c == ')' {
-                    let res = reduce_stack(&mut stack, order_matters);
-                    stack.push(res);
+    fn parse_prefix(&mut self) -> Result<Expr, Error> {
+        match self.next()? {
+            (_, Token::Num(num)) => Ok(Expr::Num(num)),
+            (_, Token::Op('-')) => {
+                let operand = self.parse_expr(self.precedence.unary)?;
+                Ok(Expr::Neg(Box::new(operand)))
+            }
+            (_, Token::Open) => {
+                let expr = self.parse_expr(0)?;
+                match self.next()? {
+                    (_, Token::Close) => Ok(expr),
+                    (pos, token) => Err(Error::UnexpectedToken(pos, token)),
                 }
-                pos = idx + 1;
             }
-            if line.len() > pos {
-                let num = line[pos..line.len()].parse().unwrap();
-                stack.push(Entry::Num(num));
+            (pos, token) => Err(Error::UnexpectedToken(pos, token)),
+        }
+    }
+
+    fn parse_expr(&mut self, min_power: u8) -> Result<Expr, Error> {
+        let mut lhs = self.parse_prefix()?;
+        while let Some(&(pos, token)) = self.tokens.get(self.pos) {
+            let op = match token {
+                Token::Op(op) => op,
+                Token::Close => break,
+                _ => return Err(Error::UnexpectedToken(pos, token)),
+            };
+            let (power, assoc) = self
+                .precedence
+                .binary(op)
+                .ok_or(Error::UnknownOperator(pos, op))?;
+            if power < min_power || (power == min_power && assoc == Assoc::Left) {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.parse_expr(power)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+}
+
+fn parse(line: &str, precedence: &Precedence) -> Result<Expr, Error> {
+    let tokens = tokenize(line)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+        precedence,
+    };
+    let expr = parser.parse_expr(0)?;
+    match tokens.get(parser.pos) {
+        Some(&(pos, token)) => Err(Error::UnexpectedToken(pos, token)),
+        None => Ok(expr),
+    }
+}
+
+fn evaluate(expr: &Expr) -> Result<i64, Error> {
+    match expr {
+        Expr::Num(num) => Ok(*num),
+        Expr::Neg(operand) => evaluate(operand)?.checked_neg().ok_or(Error::Overflow),
+        Expr::Binary(op, lhs, rhs) => {
+            let (a, b) = (evaluate(lhs)?, evaluate(rhs)?);
+            match op {
+                '+' => a.checked_add(b).ok_or(Error::Overflow),
+                '-' => a.checked_sub(b).ok_or(Error::Overflow),
+                '*' => a.checked_mul(b).ok_or(Error::Overflow),
+                '/' if b == 0 => Err(Error::DivisionByZero),
+                '/' => Ok(a / b),
+                '^' => u32::try_from(b)
+                    .ok()
+                    .and_then(|b| a.checked_pow(b))
+                    .ok_or(Error::Overflow),
+                _ => unreachable!(),
             }
+        }
+    }
+}
 
-            if let Entry::Num(res) = reduce_stack(&mut stack, order_matters) {
-                sum[order_matters as usize] += res;
+fn main() -> io::Result<()> {
+    // The binding power of each operator must be above 0, which is reserved
+    // for the top level of an expression and parenthesized subexpressions
+    let tables = [
+        Precedence {
+            binary: vec![
+                ('+', 1, Assoc::Left),
+                ('-', 1, Assoc::Left),
+                ('*', 1, Assoc::Left),
+                ('/', 1, Assoc::Left),
+                ('^', 3, Assoc::Right),
+            ],
+            unary: 3,
+        },
+        Precedence {
+            binary: vec![
+                ('+', 2, Assoc::Left),
+                ('-', 2, Assoc::Left),
+                ('*', 1, Assoc::Left),
+                ('/', 1, Assoc::Left),
+                ('^', 3, Assoc::Right),
+            ],
+            unary: 3,
+        },
+    ];
+
+    let mut sum: [i64; 2] = [0, 0];
+    for (line_no, line) in io::stdin().lock().lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        for (idx, precedence) in tables.iter().enumerate() {
+            match parse(&line, precedence).and_then(|expr| evaluate(&expr)) {
+                Ok(result) => sum[idx] += result,
+                Err(err) => eprintln!("Line {}: {}", line_no + 1, err),
             }
         }
     }
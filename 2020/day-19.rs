@@ -1,18 +1,22 @@
+use regex::Regex;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::env;
 use std::io::{self, Read};
+use std::time::Instant;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Rule {
     Seq(Vec<usize>),
-    Or(Vec<usize>, Vec<usize>),
+    Or(Vec<Vec<usize>>),
     Char(char),
 }
 
 type Rules = HashMap<usize, Rule>;
 
 fn validate_message(rules: &Rules, message: &str, mut to_match: VecDeque<usize>) -> bool {
-    if message.len() == 0 || to_match.len() == 0 {
+    if message.is_empty() || to_match.is_empty() {
         return message.len() == to_match.len();
     }
 
@@ -24,15 +28,10 @@ fn validate_message(rules: &Rules, message: &str, mut to_match: VecDeque<usize>)
             }
             validate_message(rules, message, to_match)
         }
-        Rule::Or(option_a, option_b) => {
-            let to_match_a = option_a.iter().chain(to_match.iter()).copied().collect();
-            if validate_message(rules, message, to_match_a) {
-                true
-            } else {
-                let to_match_b = option_b.iter().chain(to_match.iter()).copied().collect();
-                validate_message(rules, message, to_match_b)
-            }
-        }
+        Rule::Or(options) => options.iter().any(|option| {
+            let to_match = option.iter().chain(to_match.iter()).copied().collect();
+            validate_message(rules, message, to_match)
+        }),
         Rule::Char(c) => {
             if *c == message.chars().next().unwrap() {
                 validate_message(rules, &message[1..], to_match)
@@ -44,44 +43,295 @@ fn validate_message(rules: &Rules, message: &str, mut to_match: VecDeque<usize>)
 }
 
 fn parse_rule(input: &str) -> Rule {
+    let parse_seq = |seq: &str| -> Vec<usize> {
+        seq.split(' ')
+            .flat_map(|n| n.parse::<usize>().ok())
+            .collect()
+    };
     if input.contains('|') {
-        let mut res: Vec<Vec<usize>> = input
-            .split(" | ")
-            .map(|or| {
-                or.split(' ')
-                    .flat_map(|n| n.parse::<usize>().ok())
-                    .collect::<Vec<usize>>()
-            })
-            .collect();
-        Rule::Or(res.pop().unwrap(), res.pop().unwrap())
+        Rule::Or(input.split(" | ").map(parse_seq).collect())
     } else if input.contains('"') {
         Rule::Char(input.chars().nth(1).unwrap())
     } else {
-        Rule::Seq(
-            input
-                .split(' ')
-                .flat_map(|n| n.parse::<usize>().ok())
-                .collect(),
-        )
+        Rule::Seq(parse_seq(input))
+    }
+}
+
+fn sub_rules(rule: &Rule) -> Vec<usize> {
+    match rule {
+        Rule::Seq(seq) => seq.clone(),
+        Rule::Or(options) => options.concat(),
+        Rule::Char(_) => vec![],
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+// Depth-first cycle check: reaching a rule that is still being visited means
+// it refers back to itself. Every rule is expanded once, so shared subrules
+// don't make this exponential.
+fn is_recursive(rules: &Rules, id: usize, visits: &mut HashMap<usize, Visit>) -> bool {
+    match visits.get(&id) {
+        Some(Visit::InProgress) => return true,
+        Some(Visit::Done) => return false,
+        None => {}
+    }
+    visits.insert(id, Visit::InProgress);
+    let recursive = sub_rules(&rules[&id])
+        .into_iter()
+        .any(|sub_rule| is_recursive(rules, sub_rule, visits));
+    visits.insert(id, Visit::Done);
+    recursive
+}
+
+// The regex repeats the text of a subrule at every place it is used, so it
+// can grow exponentially when subrules are shared. Gives up past this length.
+const MAX_REGEX_LEN: usize = 1 << 16;
+
+fn to_regex(rules: &Rules, id: usize, cache: &mut HashMap<usize, String>) -> Option<String> {
+    if let Some(regex) = cache.get(&id) {
+        return Some(regex.clone());
+    }
+    let mut seq_to_regex = |seq: &Vec<usize>| -> Option<String> {
+        seq.iter()
+            .map(|&sub_rule| to_regex(rules, sub_rule, cache))
+            .collect()
+    };
+    let regex = match &rules[&id] {
+        Rule::Char(c) => regex::escape(&c.to_string()),
+        Rule::Seq(seq) => seq_to_regex(seq)?,
+        Rule::Or(options) => format!(
+            "(?:{})",
+            options
+                .iter()
+                .map(seq_to_regex)
+                .collect::<Option<Vec<_>>>()?
+                .join("|")
+        ),
+    };
+    if regex.len() > MAX_REGEX_LEN {
+        return None;
     }
+    cache.insert(id, regex.clone());
+    Some(regex)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: usize,
+    option: usize,
+    dot: usize,
+    origin: usize,
+}
+
+// General context-free grammar recognizer, which handles left, right and
+// center recursion in polynomial time
+struct Earley {
+    // Every non-terminal rule as a list of alternative sequences
+    productions: HashMap<usize, Vec<Vec<usize>>>,
+    terminals: HashMap<usize, char>,
+}
+
+impl Earley {
+    fn new(rules: &Rules) -> Self {
+        let mut productions = HashMap::new();
+        let mut terminals = HashMap::new();
+        for (&id, rule) in rules {
+            match rule {
+                Rule::Char(c) => {
+                    terminals.insert(id, *c);
+                }
+                Rule::Seq(seq) => {
+                    productions.insert(id, vec![seq.clone()]);
+                }
+                Rule::Or(options) => {
+                    productions.insert(id, options.clone());
+                }
+            }
+        }
+        Earley {
+            productions,
+            terminals,
+        }
+    }
+
+    fn next_symbol(&self, item: &Item) -> Option<usize> {
+        self.productions[&item.rule][item.option]
+            .get(item.dot)
+            .copied()
+    }
+
+    fn matches(&self, message: &str, start: usize) -> bool {
+        let chars: Vec<char> = message.chars().collect();
+        let mut chart: Vec<Vec<Item>> = vec![vec![]; chars.len() + 1];
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); chars.len() + 1];
+        let mut add = |chart: &mut Vec<Vec<Item>>, pos: usize, item: Item| {
+            if seen[pos].insert(item) {
+                chart[pos].push(item);
+            }
+        };
+
+        if let Some(options) = self.productions.get(&start) {
+            for option in 0..options.len() {
+                let item = Item {
+                    rule: start,
+                    option,
+                    dot: 0,
+                    origin: 0,
+                };
+                add(&mut chart, 0, item);
+            }
+        } else {
+            return chars.len() == 1 && self.terminals.get(&start) == chars.first();
+        }
+
+        for pos in 0..=chars.len() {
+            let mut idx = 0;
+            while idx < chart[pos].len() {
+                let item = chart[pos][idx];
+                idx += 1;
+                match self.next_symbol(&item) {
+                    Some(symbol) if self.terminals.contains_key(&symbol) => {
+                        // Scan
+                        if pos < chars.len() && self.terminals[&symbol] == chars[pos] {
+                            let next = Item {
+                                dot: item.dot + 1,
+                                ..item
+                            };
+                            add(&mut chart, pos + 1, next);
+                        }
+                    }
+                    Some(symbol) => {
+                        // Predict
+                        for option in 0..self.productions[&symbol].len() {
+                            let next = Item {
+                                rule: symbol,
+                                option,
+                                dot: 0,
+                                origin: pos,
+                            };
+                            add(&mut chart, pos, next);
+                        }
+                    }
+                    None => {
+                        // Complete
+                        let parents: Vec<Item> = chart[item.origin]
+                            .iter()
+                            .filter(|parent| self.next_symbol(parent) == Some(item.rule))
+                            .copied()
+                            .collect();
+                        for parent in parents {
+                            let next = Item {
+                                dot: parent.dot + 1,
+                                ..parent
+                            };
+                            add(&mut chart, pos, next);
+                        }
+                    }
+                }
+            }
+        }
+
+        chart[chars.len()]
+            .iter()
+            .any(|item| item.rule == start && item.origin == 0 && self.next_symbol(item).is_none())
+    }
+}
+
+enum Matcher {
+    Regex(Regex),
+    Earley(Earley),
+}
+
+impl Matcher {
+    // Uses a regex for grammars without recursion when it stays small enough
+    // to build, and the Earley parser for everything else
+    fn compile(rules: &Rules) -> Matcher {
+        if !is_recursive(rules, 0, &mut HashMap::new()) {
+            let regex = to_regex(rules, 0, &mut HashMap::new())
+                .and_then(|regex| Regex::new(&format!("^{}$", regex)).ok());
+            if let Some(regex) = regex {
+                return Matcher::Regex(regex);
+            }
+        }
+        Matcher::Earley(Earley::new(rules))
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Matcher::Regex(_) => "regex",
+            Matcher::Earley(_) => "Earley parser",
+        }
+    }
+
+    fn matches(&self, message: &str) -> bool {
+        match self {
+            Matcher::Regex(regex) => regex.is_match(message),
+            Matcher::Earley(earley) => earley.matches(message, 0),
+        }
+    }
+}
+
+fn count_matches(rules: &Rules, messages: &[&str], compare: bool) {
+    if compare {
+        let start = Instant::now();
+        let count = messages
+            .iter()
+            .filter(|message| validate_message(rules, message, VecDeque::from(vec![0])))
+            .count();
+        println!(
+            "  Backtracking: {} valid messages in {:?}",
+            count,
+            start.elapsed()
+        );
+    }
+
+    let start = Instant::now();
+    let matcher = Matcher::compile(rules);
+    let count = messages
+        .iter()
+        .filter(|message| matcher.matches(message))
+        .count();
+    println!(
+        "  Compiled {}: {} valid messages in {:?}",
+        matcher.name(),
+        count,
+        start.elapsed()
+    );
 }
 
 fn main() -> io::Result<()> {
+    // Also times the original backtracking matcher, which can be exponential
+    let compare = env::args().any(|arg| arg == "--compare");
+
     let mut input = String::new();
     io::stdin().lock().read_to_string(&mut input)?;
 
     if let [rules, messages] = input.split("\n\n").collect::<Vec<&str>>()[..] {
         let mut rule_lookup: Rules = HashMap::new();
-        for rule in rules.split("\n") {
+        for rule in rules.split('\n') {
             if let [key, value] = rule.split(": ").collect::<Vec<&str>>()[..] {
                 rule_lookup.insert(key.parse().unwrap(), parse_rule(value));
             }
         }
-        let valid_count = messages
-            .split("\n")
-            .filter(|message| validate_message(&rule_lookup, &message, VecDeque::from(vec![0])))
-            .count();
-        println!("There are {} valid messages", valid_count);
+        let messages: Vec<&str> = messages.lines().filter(|m| !m.is_empty()).collect();
+
+        println!("(1) Matching messages against the original rules");
+        count_matches(&rule_lookup, &messages, compare);
+
+        if [8, 11, 31, 42]
+            .iter()
+            .all(|id| rule_lookup.contains_key(id))
+        {
+            rule_lookup.insert(8, parse_rule("42 | 42 8"));
+            rule_lookup.insert(11, parse_rule("42 31 | 42 11 31"));
+            println!("(2) Matching messages against the recursive rules");
+            count_matches(&rule_lookup, &messages, compare);
+        }
     }
 
     Ok(())
@@ -1,45 +1,170 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::env;
 use std::fmt;
+use std::fs;
 use std::io::{self, Read};
 
+// Fixed-length row of pixels, packed into 64-bit words so that images of any
+// size can be assembled
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Row {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl Row {
+    fn new(len: usize) -> Row {
+        Row {
+            len,
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn get(&self, idx: usize) -> bool {
+        self.words[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    fn set(&mut self, idx: usize, value: bool) {
+        if value {
+            self.words[idx / 64] |= 1 << (idx % 64);
+        } else {
+            self.words[idx / 64] &= !(1 << (idx % 64));
+        }
+    }
+
+    fn count_ones(&self) -> u32 {
+        self.words.iter().map(|word| word.count_ones()).sum()
+    }
+
+    fn reversed(&self) -> Row {
+        (0..self.len).rev().map(|idx| self.get(idx)).collect()
+    }
+}
+
+impl std::iter::FromIterator<bool> for Row {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Row {
+        let bits: Vec<bool> = iter.into_iter().collect();
+        let mut row = Row::new(bits.len());
+        for (idx, bit) in bits.into_iter().enumerate() {
+            row.set(idx, bit);
+        }
+        row
+    }
+}
+
 #[derive(Clone)]
 struct Tile {
     id: i64,
     size: usize,
-    data: Vec<u128>,
+    data: Vec<Row>,
 }
 
-const PATTERN: &'static str = "
-                  # 
-#    ##    ##    ###
- #  #  #  #  #  #   ";
-const PATTERN_LEN: usize = 20;
+#[derive(Debug)]
+enum AssemblyError {
+    NotSquare(usize),
+    // An edge shared by more than two tiles makes the placement ambiguous
+    AmbiguousEdge(i64),
+    NoCorner,
+    NoMatch(usize, usize),
+}
 
-fn parse_input_line(line: &str) -> u128 {
-    line.chars()
-        .rev()
-        .fold(0, |acc, c| acc << 1 | (if c == '#' { 1 } else { 0 }))
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssemblyError::NotSquare(count) => write!(f, "{} tiles do not form a square", count),
+            AssemblyError::AmbiguousEdge(id) => {
+                write!(f, "tile {} shares an edge with several tiles", id)
+            }
+            AssemblyError::NoCorner => write!(f, "no tile has two unmatched edges"),
+            AssemblyError::NoMatch(row, col) => {
+                write!(f, "no tile fits at row {}, column {}", row, col)
+            }
+        }
+    }
 }
 
-impl Tile {
-    fn parse(input: &str) -> Tile {
-        let input = input.split("\n").collect::<Vec<&str>>();
-        Tile {
-            id: input[0]["Tile ".len()..input[0].len() - 1].parse().unwrap(),
-            size: input[1].len(),
-            data: input.into_iter().skip(1).map(parse_input_line).collect(),
+// Used when no pattern files are given on the command line
+const SEA_MONSTER: &str = "                  # \n#    ##    ##    ###\n #  #  #  #  #  #   ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Orientation {
+    // Clockwise quarter turns, applied before the flip
+    rotations: usize,
+    flipped: bool,
+}
+
+impl Orientation {
+    fn all() -> Vec<Orientation> {
+        (0..4)
+            .flat_map(|rotations| {
+                vec![false, true]
+                    .into_iter()
+                    .map(move |flipped| Orientation { rotations, flipped })
+            })
+            .collect()
+    }
+
+    // Maps a position in an image of the given size seen in this orientation
+    // back to the same pixel in the original image
+    fn to_original(self, size: usize, row: usize, col: usize) -> (usize, usize) {
+        let mut pos = if self.flipped {
+            (size - row - 1, col)
+        } else {
+            (row, col)
+        };
+        for _ in 0..self.rotations {
+            pos = (size - pos.1 - 1, pos.0);
+        }
+        pos
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rotated {}°", self.rotations * 90)?;
+        if self.flipped {
+            write!(f, ", flipped")?;
         }
+        Ok(())
+    }
+}
+
+fn parse_input_line(line: &str) -> Row {
+    line.chars().map(|c| c == '#').collect()
+}
+
+impl Tile {
+    fn parse(input: &str) -> Option<Tile> {
+        let mut lines = input.lines();
+        let header = lines.next()?;
+        let id = header
+            .strip_prefix("Tile ")?
+            .strip_suffix(':')?
+            .parse()
+            .ok()?;
+        let data: Vec<Row> = lines.map(parse_input_line).collect();
+        Some(Tile {
+            id,
+            size: data.len(),
+            data,
+        })
+    }
+
+    fn get(&self, row: usize, col: usize) -> bool {
+        self.data[row].get(col)
     }
 
+    // Clockwise
     fn rotated(&self) -> Tile {
         Tile {
             id: self.id,
             size: self.size,
             data: (0..self.size)
-                .map(|i| {
-                    (0..self.size).fold(0, |acc, j| {
-                        let bit = self.data[(self.size - j - 1)] & (1 << i);
-                        acc | (bit >> i) << j
-                    })
+                .map(|row| {
+                    (0..self.size)
+                        .map(|col| self.get(self.size - col - 1, row))
+                        .collect()
                 })
                 .collect(),
         }
@@ -49,193 +174,346 @@ impl Tile {
         Tile {
             id: self.id,
             size: self.size,
-            data: self.data.iter().copied().rev().collect(),
+            data: self.data.iter().cloned().rev().collect(),
         }
     }
 
-    fn matches_right_edge(&self, other: &Tile) -> bool {
-        (0..self.size).all(|i| {
-            ((self.data[i] & 1) << (self.size - 1)) == (other.data[i] & (1 << (self.size - 1)))
-        })
+    fn oriented(&self, orientation: Orientation) -> Tile {
+        let mut tile = self.clone();
+        for _ in 0..orientation.rotations {
+            tile = tile.rotated();
+        }
+        if orientation.flipped {
+            tile.flipped()
+        } else {
+            tile
+        }
+    }
+
+    fn orientations(&self) -> Vec<Tile> {
+        Orientation::all()
+            .into_iter()
+            .map(|orientation| self.oriented(orientation))
+            .collect()
+    }
+
+    fn top(&self) -> Row {
+        self.data[0].clone()
+    }
+
+    fn bottom(&self) -> Row {
+        self.data[self.size - 1].clone()
     }
 
-    fn matches_bottom_edge(&self, other: &Tile) -> bool {
-        self.data[0] == other.data[self.size - 1]
+    fn left(&self) -> Row {
+        (0..self.size).map(|row| self.get(row, 0)).collect()
+    }
+
+    fn right(&self) -> Row {
+        (0..self.size)
+            .map(|row| self.get(row, self.size - 1))
+            .collect()
+    }
+
+    fn edges(&self) -> [Row; 4] {
+        [self.top(), self.right(), self.bottom(), self.left()]
+    }
+
+    fn to_pbm(&self) -> String {
+        let rows: Vec<String> = self
+            .data
+            .iter()
+            .map(|row| {
+                (0..self.size)
+                    .map(|col| if row.get(col) { "1" } else { "0" })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        format!("P1\n{} {}\n{}\n", self.size, self.size, rows.join("\n"))
     }
 }
 
 impl fmt::Debug for Tile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for i in 0..self.size {
-            if self.size == 10 {
-                f.write_fmt(format_args!("{:010b}\n", self.data[i].reverse_bits() >> 22))?;
-            } else {
-                f.write_fmt(format_args!("{:024b}\n", self.data[i].reverse_bits() >> 8))?;
+        for row in &self.data {
+            for col in 0..self.size {
+                write!(f, "{}", if row.get(col) { '#' } else { '.' })?;
             }
+            writeln!(f)?;
         }
         Ok(())
     }
 }
 
-fn solve(tiles: &Vec<Tile>, solution: &mut Vec<Tile>, grid_size: usize) -> bool {
-    if solution.len() > 0 {
-        let last_idx = solution.len() - 1;
-        if last_idx % grid_size > 0
-            && !solution[last_idx].matches_right_edge(&solution[last_idx - 1])
-        {
-            return false;
+// An edge reads the same in both directions once its tile gets flipped
+fn edge_signature(edge: &Row) -> Row {
+    cmp::min(edge.clone(), edge.reversed())
+}
+
+struct EdgeIndex {
+    tiles_by_edge: HashMap<Row, Vec<usize>>,
+}
+
+impl EdgeIndex {
+    fn new(tiles: &[Tile]) -> Result<EdgeIndex, AssemblyError> {
+        let mut tiles_by_edge: HashMap<Row, Vec<usize>> = HashMap::new();
+        for (idx, tile) in tiles.iter().enumerate() {
+            for edge in tile.edges().iter() {
+                tiles_by_edge
+                    .entry(edge_signature(edge))
+                    .or_default()
+                    .push(idx);
+            }
         }
-        if last_idx > grid_size
-            && !solution[last_idx].matches_bottom_edge(&solution[last_idx - grid_size])
-        {
-            return false;
+        for indices in tiles_by_edge.values() {
+            if indices.len() > 2 {
+                return Err(AssemblyError::AmbiguousEdge(tiles[indices[0]].id));
+            }
         }
+        Ok(EdgeIndex { tiles_by_edge })
     }
 
-    if solution.len() == tiles.len() {
-        return true;
+    fn is_unmatched(&self, edge: &Row) -> bool {
+        self.tiles_by_edge[&edge_signature(edge)].len() == 1
     }
 
-    for i in 0..tiles.len() {
-        // TODO: generate all variations with iterator and reuse in find_and_remove_pattern
-        let curr_tile = &tiles[i];
-        if solution.iter().any(|t| t.id == curr_tile.id) {
-            continue;
-        }
+    fn neighbour(&self, tile_idx: usize, edge: &Row) -> Option<usize> {
+        self.tiles_by_edge[&edge_signature(edge)]
+            .iter()
+            .copied()
+            .find(|&idx| idx != tile_idx)
+    }
 
-        solution.push(curr_tile.clone());
-        if solve(tiles, solution, grid_size) {
-            return true;
-        }
+    fn corners(&self, tiles: &[Tile]) -> Vec<usize> {
+        (0..tiles.len())
+            .filter(|&idx| {
+                let edges = tiles[idx].edges();
+                edges.iter().filter(|edge| self.is_unmatched(edge)).count() == 2
+            })
+            .collect()
+    }
+}
 
-        let flipped = curr_tile.flipped();
-        *solution.last_mut().unwrap() = flipped;
-        if solve(tiles, solution, grid_size) {
-            return true;
-        }
+// Starts from a corner turned so that its unmatched edges face outwards, then
+// fills the grid row by row, looking up each neighbour by its shared edge
+fn assemble(tiles: &[Tile]) -> Result<Vec<Vec<Tile>>, AssemblyError> {
+    let grid_size = (tiles.len() as f64).sqrt().round() as usize;
+    if grid_size * grid_size != tiles.len() || grid_size == 0 {
+        return Err(AssemblyError::NotSquare(tiles.len()));
+    }
 
-        let mut rotated = curr_tile.clone();
-        for _ in 0..3 {
-            rotated = rotated.rotated();
-            *solution.last_mut().unwrap() = rotated.clone();
-            if solve(tiles, solution, grid_size) {
-                return true;
-            }
+    let index = EdgeIndex::new(tiles)?;
+    let corner = *index
+        .corners(tiles)
+        .first()
+        .ok_or(AssemblyError::NoCorner)?;
+    let corner_tile = tiles[corner]
+        .orientations()
+        .into_iter()
+        .find(|tile| index.is_unmatched(&tile.top()) && index.is_unmatched(&tile.left()))
+        .ok_or(AssemblyError::NoCorner)?;
 
-            *solution.last_mut().unwrap() = rotated.flipped();
-            if solve(tiles, solution, grid_size) {
-                return true;
+    let mut grid: Vec<Vec<Tile>> = vec![];
+    let mut placed: Vec<Vec<usize>> = vec![];
+    for row in 0..grid_size {
+        grid.push(vec![]);
+        placed.push(vec![]);
+        for col in 0..grid_size {
+            if row == 0 && col == 0 {
+                grid[0].push(corner_tile.clone());
+                placed[0].push(corner);
+                continue;
             }
-        }
 
-        solution.pop();
+            let (anchor, edge) = if col > 0 {
+                (placed[row][col - 1], grid[row][col - 1].right())
+            } else {
+                (placed[row - 1][col], grid[row - 1][col].bottom())
+            };
+            let next = index
+                .neighbour(anchor, &edge)
+                .ok_or(AssemblyError::NoMatch(row, col))?;
+            let tile = tiles[next]
+                .orientations()
+                .into_iter()
+                .find(|tile| {
+                    let fits_left = col == 0 || tile.left() == grid[row][col - 1].right();
+                    let fits_top = row == 0 || tile.top() == grid[row - 1][col].bottom();
+                    fits_left && fits_top
+                })
+                .ok_or(AssemblyError::NoMatch(row, col))?;
+            grid[row].push(tile);
+            placed[row].push(next);
+        }
     }
-
-    false
+    Ok(grid)
 }
 
-fn merge_solution(solution: &Vec<Tile>, grid_size: usize) -> Tile {
-    let tile_size = solution[0].size;
-    let reduced_tile_size = tile_size - 2;
-    let merged_size = reduced_tile_size * grid_size;
+// Drops the border of every tile and joins them into a single image
+fn merge_solution(grid: &[Vec<Tile>]) -> Tile {
+    let reduced_tile_size = grid[0][0].size - 2;
+    let merged_size = reduced_tile_size * grid.len();
     Tile {
         id: 0,
         size: merged_size,
         data: (0..merged_size)
-            .map(|i| {
-                (0..grid_size).fold(0, |acc, j| -> u128 {
-                    let tile = (i / reduced_tile_size) * grid_size + j;
-                    let row = solution[tile].data[i % reduced_tile_size + 1];
-                    let subsection = (row & ((1 << tile_size - 1) - 1)) >> 1;
-                    acc | subsection.wrapping_shl((j * reduced_tile_size) as u32)
-                })
+            .map(|row| {
+                let tiles = &grid[row / reduced_tile_size];
+                (0..merged_size)
+                    .map(|col| {
+                        tiles[col / reduced_tile_size]
+                            .get(row % reduced_tile_size + 1, col % reduced_tile_size + 1)
+                    })
+                    .collect()
             })
             .collect(),
     }
 }
 
-fn find_and_remove_pattern_impl(
-    input: &Tile,
-    pattern: &Vec<u128>,
-    pattern_len: usize,
-) -> Option<Tile> {
-    let mut found: Vec<(usize, usize)> = Vec::new();
-    for i in 0..(input.size - pattern.len()) {
-        for j in 0..(input.size - pattern_len) {
-            if pattern.iter().enumerate().all(|(k, p)| {
-                input.data[i + k].wrapping_shr((input.size - pattern_len - j) as u32) & p == *p
-            }) {
-                found.push((i, j));
-            }
-        }
-    }
+struct Pattern {
+    name: String,
+    width: usize,
+    height: usize,
+    // Positions of the '#' cells, every other cell matches anything
+    cells: Vec<(usize, usize)>,
+}
 
-    if found.len() > 0 {
-        let mut result = input.clone();
-        for (i, j) in found {
-            for (k, p) in pattern.iter().enumerate() {
-                result.data[i + k] &= !(p << (input.size - pattern_len - j));
-            }
+impl Pattern {
+    fn parse(name: &str, input: &str) -> Pattern {
+        let lines: Vec<&str> = input.lines().collect();
+        let cells = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(col, _)| (row, col))
+            })
+            .collect::<Vec<_>>();
+        Pattern {
+            name: name.to_string(),
+            width: cells.iter().map(|&(_, col)| col + 1).max().unwrap_or(0),
+            height: cells.iter().map(|&(row, _)| row + 1).max().unwrap_or(0),
+            cells,
         }
-        Some(result)
-    } else {
-        None
     }
-}
 
-fn find_and_remove_pattern(input: &Tile, pattern: &Vec<u128>, pattern_len: usize) -> Tile {
-    if let Some(result) = find_and_remove_pattern_impl(input, pattern, pattern_len) {
-        return result;
+    fn matches_at(&self, image: &Tile, row: usize, col: usize) -> bool {
+        self.cells.iter().all(|&(i, j)| image.get(row + i, col + j))
     }
+}
 
-    let flipped = input.flipped();
-    if let Some(result) = find_and_remove_pattern_impl(&flipped, pattern, pattern_len) {
-        return result;
-    }
+#[derive(Debug)]
+struct Match {
+    pattern: usize,
+    orientation: Orientation,
+    // Top left corner of the pattern in the oriented image
+    row: usize,
+    col: usize,
+}
 
-    let mut rotated = input.clone();
-    for _ in 0..3 {
-        rotated = rotated.rotated();
-        if let Some(result) = find_and_remove_pattern_impl(&rotated, pattern, pattern_len) {
-            return result;
+fn find_patterns(image: &Tile, patterns: &[Pattern]) -> Vec<Match> {
+    let mut found = vec![];
+    for orientation in Orientation::all() {
+        let oriented = image.oriented(orientation);
+        for (idx, pattern) in patterns.iter().enumerate() {
+            if pattern.cells.is_empty() || pattern.width > image.size || pattern.height > image.size
+            {
+                continue;
+            }
+            for row in 0..=(image.size - pattern.height) {
+                for col in 0..=(image.size - pattern.width) {
+                    if pattern.matches_at(&oriented, row, col) {
+                        found.push(Match {
+                            pattern: idx,
+                            orientation,
+                            row,
+                            col,
+                        });
+                    }
+                }
+            }
         }
+    }
+    found
+}
 
-        if let Some(result) = find_and_remove_pattern_impl(&rotated.flipped(), pattern, pattern_len)
-        {
-            return result;
+// Counts the '#' cells that are not part of any match. Matches may overlap
+// and may come from different orientations, so the covered cells are mapped
+// back to the original image before being counted once.
+fn roughness(image: &Tile, patterns: &[Pattern], matches: &[Match]) -> u32 {
+    let mut covered: Vec<Row> = vec![Row::new(image.size); image.size];
+    for m in matches {
+        for &(i, j) in &patterns[m.pattern].cells {
+            let (row, col) = m.orientation.to_original(image.size, m.row + i, m.col + j);
+            covered[row].set(col, true);
         }
     }
-
-    unreachable!()
+    let total: u32 = image.data.iter().map(Row::count_ones).sum();
+    total - covered.iter().map(Row::count_ones).sum::<u32>()
 }
 
 fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let export_path = args
+        .iter()
+        .position(|arg| arg == "--export")
+        .and_then(|idx| args.get(idx + 1).cloned());
+    let mut patterns = vec![];
+    for (idx, arg) in args.iter().enumerate() {
+        if arg == "--pattern" {
+            if let Some(path) = args.get(idx + 1) {
+                patterns.push(Pattern::parse(path, &fs::read_to_string(path)?));
+            }
+        }
+    }
+    if patterns.is_empty() {
+        patterns.push(Pattern::parse("sea monster", SEA_MONSTER));
+    }
+
     let mut input = String::new();
     io::stdin().lock().read_to_string(&mut input)?;
-    let mut tiles: Vec<Tile> = Vec::new();
-    for tile in input.split("\n\n") {
-        tiles.push(Tile::parse(tile));
+    let tiles: Vec<Tile> = input.split("\n\n").filter_map(Tile::parse).collect();
+
+    let grid = match assemble(&tiles) {
+        Ok(grid) => grid,
+        Err(err) => {
+            eprintln!("Could not assemble the image: {}", err);
+            return Ok(());
+        }
+    };
+
+    println!("(1) Found solution:");
+    for row in &grid {
+        let ids: Vec<i64> = row.iter().map(|tile| tile.id).collect();
+        println!("  {:?}", ids);
     }
+    let last = grid.len() - 1;
+    println!(
+        "Product of corners is {}",
+        grid[0][0].id * grid[0][last].id * grid[last][0].id * grid[last][last].id
+    );
 
-    let grid_size = (tiles.len() as f32).sqrt() as usize;
-    let mut solution = vec![];
-    if solve(&tiles, &mut solution, grid_size) {
-        let solution_ids: Vec<i64> = solution.iter().map(|s| s.id).collect();
-        println!("(1) Found solution: {:?}", solution_ids);
+    let merged = merge_solution(&grid);
+    if let Some(path) = export_path {
+        fs::write(&path, merged.to_pbm())?;
+        println!("Exported the assembled image to {}", path);
+    }
+
+    let matches = find_patterns(&merged, &patterns);
+    for m in &matches {
         println!(
-            "Product of corners is {}",
-            solution_ids[0]
-                * solution_ids[grid_size - 1]
-                * solution_ids[grid_size * (grid_size - 1)]
-                * solution_ids[grid_size * grid_size - 1]
+            "Found {} at row {}, column {} ({})",
+            patterns[m.pattern].name, m.row, m.col, m.orientation
         );
-
-        let merged = merge_solution(&solution, grid_size);
-        let pattern: Vec<u128> = PATTERN.split("\n").skip(1).map(parse_input_line).collect();
-        let remains = find_and_remove_pattern(&merged, &pattern, PATTERN_LEN);
-        let waters: u32 = remains.data.iter().map(|row| row.count_ones()).sum();
-        println!("(2) Water that is not part of the monster {:?}", waters);
     }
+    println!(
+        "(2) Roughness after removing {} matches is {}",
+        matches.len(),
+        roughness(&merged, &patterns, &matches)
+    );
 
     Ok(())
 }
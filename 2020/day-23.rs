@@ -1,121 +1,197 @@
-use std::char;
-use std::collections::HashMap;
+use std::env;
+use std::fmt;
 use std::io::{self, Read};
-use std::ops::Range;
 
-struct Node {
-    val: u32,
-    next: usize,
+const NIL: u32 = u32::MAX;
+
+// Circular singly linked list over dense integer labels: the successor of
+// label n is stored at index n, so every link operation is O(1) and no label
+// lookup is needed
+struct CircularList {
+    next: Vec<u32>,
+    head: u32,
 }
 
-struct Ring {
-    nodes: Vec<Node>,
-    lookup: HashMap<u32, usize>,
+// Detached chain of labels, still linked from first to last
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    first: u32,
+    last: u32,
 }
 
-impl Ring {
-    fn new(data: &Vec<u32>) -> Ring {
-        let mut nodes: Vec<_> = data
-            .iter()
-            .enumerate()
-            .map(|(i, &val)| Node { val, next: i + 1 })
-            .collect();
-        nodes.last_mut().map(|el| el.next = 0);
-        Ring {
-            nodes,
-            lookup: data.iter().enumerate().map(|(i, &val)| (val, i)).collect(),
+impl CircularList {
+    fn new(labels: &[u32]) -> CircularList {
+        let capacity = labels.iter().max().map_or(0, |&max| max as usize + 1);
+        let mut next = vec![NIL; capacity];
+        for (idx, &label) in labels.iter().enumerate() {
+            next[label as usize] = labels[(idx + 1) % labels.len()];
+        }
+        CircularList {
+            next,
+            head: labels.first().copied().unwrap_or(NIL),
         }
     }
 
-    fn len(&self) -> usize {
-        self.nodes.len()
+    fn head(&self) -> u32 {
+        self.head
     }
 
-    fn find_first_in_range(&self, range: Range<u32>, exclude_list: &Vec<u32>) -> Option<u32> {
-        range.rev().find_map(|search| {
-            if self.lookup.contains_key(&search) && !exclude_list.contains(&search) {
-                return Some(search);
-            } else {
-                None
-            }
-        })
+    fn advance(&mut self) {
+        self.head = self.next(self.head);
     }
 
-    fn remove(&mut self, from: u32, to: u32) {
-        self.nodes[self.lookup[&from]].next = self.nodes[self.lookup[&to]].next;
+    fn next(&self, label: u32) -> u32 {
+        self.next[label as usize]
     }
 
-    fn insert(&mut self, node_val: u32, to_insert: &Vec<u32>) {
-        let node_idx = self.lookup[&node_val];
-        let node_next = self.nodes[node_idx].next;
-        self.nodes[node_idx].next = self.lookup[&to_insert[0]];
-        self.nodes[self.lookup[&to_insert[to_insert.len() - 1]]].next = node_next;
+    fn label_range(&self) -> (u32, u32) {
+        let mut labels = (0..self.next.len() as u32).filter(|&label| self.next(label) != NIL);
+        let min = labels.next().unwrap_or(NIL);
+        (min, labels.next_back().unwrap_or(min))
     }
 
-    fn iter_from(&self, from_val: u32) -> RingIterator {
-        let start = &self.nodes[self.lookup[&from_val]];
-        RingIterator {
-            nodes: &self.nodes,
-            curr: &self.nodes[start.next],
+    fn insert_after(&mut self, label: u32, new_label: u32) {
+        if new_label as usize >= self.next.len() {
+            self.next.resize(new_label as usize + 1, NIL);
         }
+        self.next[new_label as usize] = self.next(label);
+        self.next[label as usize] = new_label;
     }
 
-    fn formatted_result(&self) -> String {
-        self.iter_from(1)
-            .take(self.nodes.len() - 1)
-            .flat_map(|node| char::from_digit(node, 10))
-            .collect()
+    // Unlinks the labels following `after` up to and including `last`
+    fn remove_run(&mut self, after: u32, last: u32) -> Run {
+        let first = self.next(after);
+        self.next[after as usize] = self.next(last);
+        if self.head == first {
+            self.head = self.next(after);
+        }
+        Run { first, last }
+    }
+
+    fn insert_run_after(&mut self, label: u32, run: Run) {
+        self.next[run.last as usize] = self.next(label);
+        self.next[label as usize] = run.first;
+    }
+
+    // Moves the labels following `after` up to `last` behind `target`
+    fn splice(&mut self, after: u32, last: u32, target: u32) {
+        let run = self.remove_run(after, last);
+        self.insert_run_after(target, run);
+    }
+
+    // Visits every label once, going around the ring from `label`
+    fn iter_from(&self, label: u32) -> Iter<'_> {
+        Iter {
+            list: self,
+            start: label,
+            curr: label,
+        }
+    }
+
+    fn iter(&self) -> Iter<'_> {
+        self.iter_from(self.head)
     }
 }
 
-struct RingIterator<'a> {
-    nodes: &'a Vec<Node>,
-    curr: &'a Node,
+struct Iter<'a> {
+    list: &'a CircularList,
+    start: u32,
+    curr: u32,
 }
 
-impl<'a> Iterator for RingIterator<'a> {
+impl<'a> Iterator for Iter<'a> {
     type Item = u32;
 
     fn next(&mut self) -> Option<u32> {
+        if self.curr == NIL {
+            return None;
+        }
         let curr = self.curr;
-        self.curr = &self.nodes[curr.next];
-        Some(curr.val)
+        self.curr = self.list.next(curr);
+        if self.curr == self.start {
+            self.curr = NIL;
+        }
+        Some(curr)
     }
 }
 
-fn run(mut ring: Ring, turns: usize) -> Ring {
-    let mut current = ring.nodes[0].val;
-    for _ in 0..turns {
-        let removed = ring.iter_from(current).take(3).collect::<Vec<_>>();
-        ring.remove(current, removed[removed.len() - 1]);
+// Prints the ring starting from the head, which is shown in parentheses
+impl fmt::Display for CircularList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, label) in self.iter().enumerate() {
+            match idx {
+                0 => write!(f, "({})", label)?,
+                _ => write!(f, " {}", label)?,
+            }
+        }
+        Ok(())
+    }
+}
 
-        let dest_val = ring.find_first_in_range(1..current, &removed);
-        let dest_val = dest_val.unwrap_or_else(|| {
-            ring.find_first_in_range(current..(ring.len() as u32 + 1), &removed)
-                .unwrap()
-        });
-        ring.insert(dest_val, &removed);
+fn run(mut cups: CircularList, moves: usize, verbose: bool) -> CircularList {
+    let (min, max) = cups.label_range();
+    for turn in 0..moves {
+        let current = cups.head();
+        let first = cups.next(current);
+        let second = cups.next(first);
+        let third = cups.next(second);
+        if verbose {
+            println!("-- move {} --", turn + 1);
+            println!("cups: {}", cups);
+            println!("pick up: {}, {}, {}", first, second, third);
+        }
 
-        current = ring.iter_from(current).next().unwrap();
+        let mut destination = current;
+        loop {
+            destination = if destination == min {
+                max
+            } else {
+                destination - 1
+            };
+            if ![first, second, third].contains(&destination) {
+                break;
+            }
+        }
+        if verbose {
+            println!("destination: {}\n", destination);
+        }
+
+        cups.splice(current, third, destination);
+        cups.advance();
     }
-    ring
+    cups
 }
 
 fn main() -> io::Result<()> {
+    let verbose = env::args().any(|arg| arg == "--verbose");
+
     let mut input = String::new();
     io::stdin().lock().read_to_string(&mut input)?;
 
-    let mut input = input
+    let input = input
         .chars()
         .flat_map(|c| c.to_digit(10))
         .collect::<Vec<_>>();
 
-    let result = run(Ring::new(&input), 100);
-    println!("(1) Result: {}", result.formatted_result());
-
-    input.extend(((input.len() + 1) as u32)..=1000000);
-    let result = run(Ring::new(&input), 10000000);
-    let values = result.iter_from(1).take(2).collect::<Vec<_>>();
+    let result = run(CircularList::new(&input), 100, verbose);
+    if verbose {
+        println!("-- final --\ncups: {}\n", result);
+    }
+    let labels: String = result
+        .iter_from(1)
+        .skip(1)
+        .map(|label| label.to_string())
+        .collect();
+    println!("(1) Result: {}", labels);
+
+    let mut cups = CircularList::new(&input);
+    let mut last = *input.last().unwrap();
+    for label in ((input.len() + 1) as u32)..=1000000 {
+        cups.insert_after(last, label);
+        last = label;
+    }
+    let result = run(cups, 10000000, false);
+    let values = result.iter_from(1).skip(1).take(2).collect::<Vec<_>>();
     println!("(2) Result: {}", values[0] as u64 * values[1] as u64);
 
     Ok(())
@@ -1,71 +1,166 @@
-use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::env;
 use std::io::{self, BufRead};
-use std::mem;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Acc,
+    Jmp,
+    Nop,
+}
+
+#[derive(Debug, Clone, Copy)]
 struct Instruction {
-    instr_type: String,
+    op: Op,
     value: i32,
 }
 
-fn run_program(instructions: &Vec<Instruction>) -> (i32, bool) {
-    let (mut acc, mut ip) = (0_i32, 0);
-    let mut instructions_seen = HashSet::new();
-    while ip < instructions.len() && !instructions_seen.contains(&ip) {
-        instructions_seen.insert(ip);
-        let instruction = &instructions[ip];
-        match instruction.instr_type.as_ref() {
-            "nop" => {
-                ip += 1;
-            }
-            "acc" => {
-                acc += instruction.value;
-                ip += 1;
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Terminated,
+    Loop,
+    OutOfBounds,
+}
+
+impl Instruction {
+    fn parse(line: &str) -> Option<Instruction> {
+        if let [op, value] = line.split(' ').collect::<Vec<&str>>()[..] {
+            let op = match op {
+                "acc" => Op::Acc,
+                "jmp" => Op::Jmp,
+                "nop" => Op::Nop,
+                _ => return None,
+            };
+            value.parse().ok().map(|value| Instruction { op, value })
+        } else {
+            None
+        }
+    }
+
+    fn flipped(&self) -> Option<Instruction> {
+        let op = match self.op {
+            Op::Acc => return None,
+            Op::Jmp => Op::Nop,
+            Op::Nop => Op::Jmp,
+        };
+        Some(Instruction { op, ..*self })
+    }
+
+    fn next_ip(&self, ip: usize) -> i64 {
+        match self.op {
+            Op::Jmp => ip as i64 + self.value as i64,
+            _ => ip as i64 + 1,
+        }
+    }
+}
+
+struct Vm<'a> {
+    program: &'a [Instruction],
+    acc: i32,
+    // Instruction pointers in the order they were executed
+    trace: Vec<usize>,
+}
+
+impl<'a> Vm<'a> {
+    fn new(program: &'a [Instruction]) -> Self {
+        Vm {
+            program,
+            acc: 0,
+            trace: vec![],
+        }
+    }
+
+    fn run(&mut self) -> Outcome {
+        let mut seen = vec![false; self.program.len()];
+        let mut ip: i64 = 0;
+        loop {
+            if ip == self.program.len() as i64 {
+                return Outcome::Terminated;
+            } else if ip < 0 || ip > self.program.len() as i64 {
+                return Outcome::OutOfBounds;
+            } else if seen[ip as usize] {
+                return Outcome::Loop;
             }
-            "jmp" => {
-                ip = (ip as i32 + instruction.value) as usize;
+
+            let instruction = &self.program[ip as usize];
+            seen[ip as usize] = true;
+            self.trace.push(ip as usize);
+            if instruction.op == Op::Acc {
+                self.acc += instruction.value;
             }
-            _ => unreachable!(),
+            ip = instruction.next_ip(ip as usize);
         }
     }
-    (acc, ip == instructions.len())
 }
 
-fn main() -> io::Result<()> {
-    let mut instructions = Vec::new();
-    for line in io::stdin().lock().lines() {
-        if let [instr, value_str] = line?.split(' ').collect::<Vec<&str>>()[..] {
-            if let Ok(value) = value_str.parse::<i32>() {
-                instructions.push(Instruction {
-                    instr_type: instr.to_string(),
-                    value,
-                });
+// Marks every instruction from which execution ends right after the program
+fn find_terminating(program: &[Instruction]) -> Vec<bool> {
+    let end = program.len();
+    let mut predecessors = vec![vec![]; end + 1];
+    for (ip, instruction) in program.iter().enumerate() {
+        let next = instruction.next_ip(ip);
+        if (0..=end as i64).contains(&next) {
+            predecessors[next as usize].push(ip);
+        }
+    }
+
+    let mut terminating = vec![false; end + 1];
+    let mut queue = VecDeque::from(vec![end]);
+    terminating[end] = true;
+    while let Some(ip) = queue.pop_front() {
+        for &prev in &predecessors[ip] {
+            if !terminating[prev] {
+                terminating[prev] = true;
+                queue.push_back(prev);
             }
         }
     }
+    terminating
+}
 
-    let (acc, _) = run_program(&instructions);
-    println!("(1) Accumulator is {}", acc);
+// Finds the single jmp/nop on the looping path whose flipped version jumps
+// into an instruction that leads to termination
+fn find_repair(program: &[Instruction]) -> Option<(usize, Instruction)> {
+    let terminating = find_terminating(program);
+    let mut vm = Vm::new(program);
+    vm.run();
+    vm.trace.into_iter().find_map(|ip| {
+        let flipped = program[ip].flipped()?;
+        let next = flipped.next_ip(ip);
+        ((0..=program.len() as i64).contains(&next) && terminating[next as usize])
+            .then_some((ip, flipped))
+    })
+}
 
-    for i in 0..instructions.len() {
-        let instr_type = &instructions[i].instr_type;
-        if instr_type == "acc" {
-            continue;
+fn main() -> io::Result<()> {
+    let mut program = Vec::new();
+    for line in io::stdin().lock().lines() {
+        if let Some(instruction) = Instruction::parse(&line?) {
+            program.push(instruction);
         }
+    }
 
-        let new_instr = match instr_type.as_ref() {
-            "jmp" => "nop",
-            _ => "jmp",
-        };
-        let old_instr = mem::replace(&mut instructions[i].instr_type, new_instr.to_string());
-        let (acc, did_terminate) = run_program(&instructions);
-        instructions[i].instr_type = old_instr;
-
-        if did_terminate {
-            println!("(2) Accumulator is {}", acc);
-            break;
+    let mut vm = Vm::new(&program);
+    let outcome = vm.run();
+    println!("(1) Accumulator is {} ({:?})", vm.acc, outcome);
+    if env::args().any(|arg| arg == "--trace") {
+        for ip in &vm.trace {
+            println!("  {:>4}: {:?}", ip, program[*ip]);
         }
     }
 
+    if let Some((ip, flipped)) = find_repair(&program) {
+        let original = program[ip];
+        program[ip] = flipped;
+        let mut vm = Vm::new(&program);
+        let outcome = vm.run();
+        println!(
+            "(2) Flipping instruction {} from {:?} to {:?}, accumulator is {} ({:?})",
+            ip, original.op, flipped.op, vm.acc, outcome
+        );
+    } else {
+        println!("(2) No single instruction change makes the program terminate");
+    }
+
     Ok(())
 }
@@ -2,6 +2,9 @@ use regex::Regex;
 use std::collections::HashSet;
 use std::io::{self, BufRead};
 
+#[path = "../../common/ocr.rs"]
+mod ocr;
+
 type Point = (u32, u32);
 type Grid = HashSet<Point>;
 enum Fold {
@@ -20,14 +23,20 @@ fn apply_fold(state: &Grid, fold: &Fold) -> Grid {
         .collect()
 }
 
-fn print_grid(grid: &Grid) {
+fn to_bitmap(grid: &Grid) -> Vec<Vec<bool>> {
     let max_x = *grid.iter().map(|(x, _)| x).max().unwrap();
     let max_y = *grid.iter().map(|(_, y)| y).max().unwrap();
-    for y in 0..=max_y {
+    (0..=max_y)
+        .map(|y| (0..=max_x).map(|x| grid.contains(&(x, y))).collect())
+        .collect()
+}
+
+fn print_grid(grid: &Grid) {
+    for row in to_bitmap(grid) {
         println!(
             "{}",
-            (0..=max_x)
-                .map(|x| if grid.contains(&(x, y)) { '█' } else { '.' })
+            row.iter()
+                .map(|&dot| if dot { '█' } else { '.' })
                 .collect::<String>()
         );
     }
@@ -59,8 +68,14 @@ fn main() -> io::Result<()> {
         .iter()
         .fold(state, |state, fold| apply_fold(&state, fold));
 
-    println!("(2) After applying all folds, a pattern appears");
-    print_grid(&state);
+    match ocr::recognize(&to_bitmap(&state)) {
+        Ok(code) => println!("(2) After applying all folds, the code is {}", code),
+        Err(err) => {
+            println!("(2) After applying all folds, a pattern appears");
+            print_grid(&state);
+            println!("{}", err);
+        }
+    }
 
     Ok(())
 }
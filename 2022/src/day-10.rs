@@ -1,6 +1,9 @@
 use itertools::Itertools;
 use std::io::{self, BufRead};
 
+#[path = "../../common/ocr.rs"]
+mod ocr;
+
 fn main() {
     let mut register: i32 = 1;
     let mut cycle_states: Vec<i32> = vec![];
@@ -23,20 +26,29 @@ fn main() {
         .sum();
     println!("(1) Sum of signal strengths is {}", sum);
 
-    let output = cycle_states
+    let screen: Vec<Vec<bool>> = cycle_states
         .iter()
         .enumerate()
         .map(|(idx, state)| {
             let pixel_pos = (idx % 40) as i32;
-            if (state - pixel_pos).abs() <= 1 {
-                '█'
-            } else {
-                ' '
-            }
+            (state - pixel_pos).abs() <= 1
         })
         .chunks(40)
         .into_iter()
-        .map(|chunk| chunk.collect::<String>())
-        .join("\n");
-    println!("(2) The CRT displays\n{}", output);
+        .map(|chunk| chunk.collect())
+        .collect();
+    match ocr::recognize(&screen) {
+        Ok(letters) => println!("(2) The CRT displays {}", letters),
+        Err(err) => {
+            let output = screen
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&lit| if lit { '█' } else { ' ' })
+                        .collect::<String>()
+                })
+                .join("\n");
+            println!("(2) The CRT displays\n{}\n{}", output, err);
+        }
+    }
 }
@@ -0,0 +1,169 @@
+// Recognises the block letters drawn by "read the letters" puzzles. Include
+// it in a solution with `#[path = "../../common/ocr.rs"] mod ocr;`.
+use std::fmt;
+
+const ALPHABET_6: [(char, &str); 18] = [
+    ('A', ".##.|#..#|#..#|####|#..#|#..#"),
+    ('B', "###.|#..#|###.|#..#|#..#|###."),
+    ('C', ".##.|#..#|#...|#...|#..#|.##."),
+    ('E', "####|#...|###.|#...|#...|####"),
+    ('F', "####|#...|###.|#...|#...|#..."),
+    ('G', ".##.|#..#|#...|#.##|#..#|.###"),
+    ('H', "#..#|#..#|####|#..#|#..#|#..#"),
+    ('I', "###|.#.|.#.|.#.|.#.|###"),
+    ('J', "..##|...#|...#|...#|#..#|.##."),
+    ('K', "#..#|#.#.|##..|#.#.|#.#.|#..#"),
+    ('L', "#...|#...|#...|#...|#...|####"),
+    ('O', ".##.|#..#|#..#|#..#|#..#|.##."),
+    ('P', "###.|#..#|#..#|###.|#...|#..."),
+    ('R', "###.|#..#|#..#|###.|#.#.|#..#"),
+    ('S', ".###|#...|#...|.##.|...#|###."),
+    ('U', "#..#|#..#|#..#|#..#|#..#|.##."),
+    ('Y', "#...#|#...#|.#.#.|..#..|..#..|..#.."),
+    ('Z', "####|...#|..#.|.#..|#...|####"),
+];
+
+#[rustfmt::skip]
+const ALPHABET_10: [(char, &str); 15] = [
+    ('A', "..##..|.#..#.|#....#|#....#|#....#|######|#....#|#....#|#....#|#....#"),
+    ('B', "#####.|#....#|#....#|#....#|#####.|#....#|#....#|#....#|#....#|#####."),
+    ('C', ".####.|#....#|#.....|#.....|#.....|#.....|#.....|#.....|#....#|.####."),
+    ('E', "######|#.....|#.....|#.....|#####.|#.....|#.....|#.....|#.....|######"),
+    ('F', "######|#.....|#.....|#.....|#####.|#.....|#.....|#.....|#.....|#....."),
+    ('G', ".####.|#....#|#.....|#.....|#.....|#..###|#....#|#....#|#...##|.###.#"),
+    ('H', "#....#|#....#|#....#|#....#|######|#....#|#....#|#....#|#....#|#....#"),
+    ('J', "...###|....#.|....#.|....#.|....#.|....#.|....#.|#...#.|#...#.|.###.."),
+    ('K', "#....#|#...#.|#..#..|#.#...|##....|##....|#.#...|#..#..|#...#.|#....#"),
+    ('L', "#.....|#.....|#.....|#.....|#.....|#.....|#.....|#.....|#.....|######"),
+    ('N', "#....#|##...#|##...#|#.#..#|#.#..#|#..#.#|#..#.#|#...##|#...##|#....#"),
+    ('P', "#####.|#....#|#....#|#....#|#####.|#.....|#.....|#.....|#.....|#....."),
+    ('R', "#####.|#....#|#....#|#....#|#####.|#..#..|#...#.|#...#.|#....#|#....#"),
+    ('X', "#....#|#....#|.#..#.|.#..#.|..##..|..##..|.#..#.|.#..#.|#....#|#....#"),
+    ('Z', "######|.....#|.....#|....#.|...#..|..#...|.#....|#.....|#.....|######"),
+];
+
+#[derive(Debug)]
+pub struct OcrError {
+    text: String,
+    // Index of the unknown character in the text, and its leftmost column
+    unknown: Vec<(usize, usize)>,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Could not read all letters in \"{}\":", self.text)?;
+        for (index, column) in &self.unknown {
+            write!(f, " unknown glyph #{} at column {};", index + 1, column)?;
+        }
+        Ok(())
+    }
+}
+
+// Renders a glyph in the same format as the alphabets, without blank columns
+fn normalize(rows: &[Vec<bool>], height: usize) -> String {
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let is_blank = |x: usize| rows.iter().all(|row| !row.get(x).copied().unwrap_or(false));
+    let columns: Vec<usize> = (0..width).filter(|&x| !is_blank(x)).collect();
+    let (first, last) = match (columns.first(), columns.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return String::new(),
+    };
+    (0..height)
+        .map(|y| {
+            (first..=last)
+                .map(|x| match rows.get(y).and_then(|row| row.get(x)) {
+                    Some(true) => '#',
+                    _ => '.',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+fn parse_glyph(glyph: &str) -> Vec<Vec<bool>> {
+    glyph
+        .split('|')
+        .map(|row| row.chars().map(|c| c == '#').collect())
+        .collect()
+}
+
+// Column ranges of the runs of non-blank columns
+fn blank_separated_cells(grid: &[Vec<bool>], width: usize) -> Vec<(usize, usize)> {
+    let is_blank = |x: usize| grid.iter().all(|row| !row.get(x).copied().unwrap_or(false));
+    let mut cells = vec![];
+    let mut x = 0;
+    while x < width {
+        if is_blank(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && !is_blank(x) {
+            x += 1;
+        }
+        cells.push((start, x));
+    }
+    cells
+}
+
+fn fixed_cells(width: usize, step: usize) -> Vec<(usize, usize)> {
+    (0..width)
+        .step_by(step)
+        .map(|start| (start, (start + step).min(width)))
+        .collect()
+}
+
+fn read_cells(
+    grid: &[Vec<bool>],
+    cells: &[(usize, usize)],
+    alphabet: &[(char, &str)],
+) -> Result<String, OcrError> {
+    let height = alphabet[0].1.split('|').count();
+    let mut text = String::new();
+    let mut unknown = vec![];
+    for &(start, end) in cells {
+        let rows: Vec<Vec<bool>> = grid
+            .iter()
+            .map(|row| row.iter().skip(start).take(end - start).copied().collect())
+            .collect();
+        let glyph = normalize(&rows, height);
+        if glyph.is_empty() {
+            continue;
+        }
+        match alphabet
+            .iter()
+            .find(|(_, pattern)| normalize(&parse_glyph(pattern), height) == glyph)
+        {
+            Some((letter, _)) => text.push(*letter),
+            None => {
+                unknown.push((text.chars().count(), start));
+                text.push('?');
+            }
+        }
+    }
+
+    if unknown.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError { text, unknown })
+    }
+}
+
+pub fn recognize(grid: &[Vec<bool>]) -> Result<String, OcrError> {
+    let ink_height = grid
+        .iter()
+        .rposition(|row| row.iter().any(|&pixel| pixel))
+        .map_or(0, |y| y + 1);
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    let by_blank_columns = blank_separated_cells(grid, width);
+    if ink_height > 6 {
+        return read_cells(grid, &by_blank_columns, &ALPHABET_10);
+    }
+
+    // The small font sits in cells 5 columns wide, but Y fills its whole
+    // cell and touches the next letter, so blank columns only serve as a
+    // fallback for images that don't follow that layout
+    read_cells(grid, &fixed_cells(width, 5), &ALPHABET_6)
+        .or_else(|err| read_cells(grid, &by_blank_columns, &ALPHABET_6).map_err(|_| err))
+}